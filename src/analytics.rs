@@ -0,0 +1,46 @@
+use worker::js_sys::{self, Reflect};
+use worker::wasm_bindgen::{JsCast, JsValue};
+
+const BINDING: &str = "ANALYTICS";
+
+pub fn record(
+    request: &worker::Request,
+    environment: &worker::Env,
+    response: &worker::Response,
+) -> worker::Result<()> {
+    let dataset = Reflect::get(environment, &BINDING.into())?;
+    if dataset.is_undefined() {
+        return Ok(());
+    }
+    let write_data_point: js_sys::Function =
+        Reflect::get(&dataset, &"writeDataPoint".into())?.dyn_into()?;
+
+    let url = request.url()?;
+    let headers = response.headers();
+    let bytes = headers
+        .get("content-length")?
+        .and_then(|length| length.parse::<f64>().ok())
+        .unwrap_or_default();
+    let blobs: js_sys::Array = [
+        url.path().to_owned(),
+        request.cf().country().unwrap_or_default(),
+        headers.get("x-cache")?.unwrap_or_default(),
+        request.method().to_string(),
+    ]
+    .into_iter()
+    .map(JsValue::from)
+    .collect();
+    let doubles: js_sys::Array = [f64::from(response.status_code()), bytes]
+        .into_iter()
+        .map(JsValue::from)
+        .collect();
+    let indexes: js_sys::Array =
+        std::iter::once(JsValue::from(url.host_str().unwrap_or_default())).collect();
+
+    let data_point = js_sys::Object::new();
+    Reflect::set(&data_point, &"blobs".into(), &blobs)?;
+    Reflect::set(&data_point, &"doubles".into(), &doubles)?;
+    Reflect::set(&data_point, &"indexes".into(), &indexes)?;
+    write_data_point.call1(&dataset, &data_point)?;
+    Ok(())
+}
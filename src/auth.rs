@@ -0,0 +1,43 @@
+use base64::Engine;
+use sha2::Digest;
+
+pub fn identity(
+    request: &worker::Request,
+    config: &crate::config::Config,
+) -> worker::Result<Option<String>> {
+    let Some(users) = &config.auth_users else {
+        return Ok(None);
+    };
+    let Some(credentials) = request
+        .headers()
+        .get("authorization")?
+        .and_then(|authorization| {
+            let (scheme, credentials) = authorization.split_once(' ')?;
+            scheme.eq_ignore_ascii_case("basic").then(|| {
+                base64::engine::general_purpose::STANDARD
+                    .decode(credentials.trim())
+                    .ok()
+            })?
+        })
+        .and_then(|credentials| String::from_utf8(credentials).ok())
+    else {
+        return Ok(None);
+    };
+    let Some((user, password)) = credentials.split_once(':') else {
+        return Ok(None);
+    };
+
+    let valid = users.get(user).is_some_and(|expected| {
+        sha2::Sha256::digest(expected.as_bytes()) == sha2::Sha256::digest(password.as_bytes())
+    });
+    Ok(valid.then(|| user.to_owned()))
+}
+
+pub fn challenge() -> worker::Result<worker::Response> {
+    let mut headers = worker::Headers::new();
+    headers.set(
+        "www-authenticate",
+        "Basic realm=\"listing\", charset=\"UTF-8\"",
+    )?;
+    Ok(worker::Response::error("Unauthorized", 401)?.with_headers(headers))
+}
@@ -0,0 +1,61 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const RELOAD_INTERVAL_MILLIS: u64 = 60_000;
+
+struct LoadedFile {
+    loaded_at: u64,
+    parsed: Rc<dyn Any>,
+}
+
+thread_local! {
+    static FILES: RefCell<HashMap<String, LoadedFile>> = RefCell::new(HashMap::new());
+}
+
+pub async fn load<T: 'static>(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    key: &str,
+    parse: fn(&str) -> T,
+) -> worker::Result<Rc<T>> {
+    let host = request.url()?.host_str().unwrap_or_default().to_owned();
+    let mount = crate::mounts::resolve(environment, config, &host, "")?;
+    let name = match &mount {
+        Some(mount) => format!("{}:{}", mount.binding, mount.key(key)),
+        None => String::new(),
+    };
+    let now = worker::Date::now().as_millis();
+    let cached = FILES.with(|files| {
+        files
+            .borrow()
+            .get(&name)
+            .filter(|file| now.saturating_sub(file.loaded_at) < RELOAD_INTERVAL_MILLIS)
+            .and_then(|file| file.parsed.clone().downcast::<T>().ok())
+    });
+    if let Some(parsed) = cached {
+        return Ok(parsed);
+    }
+
+    let object = match &mount {
+        Some(mount) => mount.store.get(&mount.key(key)).await?,
+        None => None,
+    };
+    let contents = match object {
+        Some((_, body)) => body.text().await?,
+        None => String::new(),
+    };
+    let parsed = Rc::new(parse(&contents));
+    FILES.with(|files| {
+        files.borrow_mut().insert(
+            name,
+            LoadedFile {
+                loaded_at: now,
+                parsed: parsed.clone(),
+            },
+        )
+    });
+    Ok(parsed)
+}
@@ -0,0 +1,18 @@
+pub fn apply(
+    config: &crate::config::Config,
+    key: &str,
+    headers: &mut worker::Headers,
+) -> worker::Result<()> {
+    if headers.has("cache-control")? {
+        return Ok(());
+    }
+
+    let cache_control = crate::extension(key)
+        .and_then(|extension| config.cache_control_extensions.get(&extension))
+        .or(config.cache_control_default.as_ref());
+
+    if let Some(cache_control) = cache_control {
+        headers.set("cache-control", cache_control)?;
+    }
+    Ok(())
+}
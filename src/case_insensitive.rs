@@ -0,0 +1,46 @@
+pub async fn find(
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<Option<String>> {
+    let (parent, name) = match path.rsplit_once('/') {
+        Some((parent, name)) => (&path[..=parent.len()], name),
+        None => ("", path),
+    };
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let wanted = name.to_lowercase();
+    let filter = crate::filter::EntryFilter::from_config(config, false);
+    let key_prefix = mount.key(parent);
+    let mut cursor = None;
+    loop {
+        let listing = mount
+            .store
+            .list(crate::store::ListOptions {
+                prefix: key_prefix.clone(),
+                delimiter: Some("/".into()),
+                cursor,
+                ..Default::default()
+            })
+            .await?;
+        let found = listing
+            .objects
+            .iter()
+            .map(|object| object.key.as_str())
+            .chain(listing.delimited_prefixes.iter().map(String::as_str))
+            .map(|key| key.strip_prefix(key_prefix.as_str()).unwrap_or(key))
+            .find(|candidate| {
+                *candidate != name
+                    && candidate.trim_end_matches('/').to_lowercase() == wanted
+                    && !filter.excludes(candidate)
+            });
+        if let Some(found) = found {
+            return Ok(Some(format!("{parent}{found}")));
+        }
+        match listing.cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(None),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use futures_util::StreamExt;
+use sha2::Digest;
+
+pub fn requested(url: &url::Url) -> bool {
+    url.query_pairs().any(|(name, _)| name == "sha256sums")
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let recursive = request
+        .url()?
+        .query_pairs()
+        .any(|(name, value)| name == "recursive" && value != "0");
+    let filter = crate::filter::EntryFilter::from_config(config, false);
+    let files = match recursive {
+        true => crate::walk::files(mount, path, &filter).await?,
+        false => crate::walk::children(mount, path, &filter).await?,
+    };
+    let mut manifest = String::new();
+    for (file, object) in files {
+        let name = &file[path.len()..];
+        let checksum = match object
+            .checksums
+            .iter()
+            .find(|(algorithm, _)| algorithm == "sha256")
+        {
+            Some((_, checksum)) => checksum.clone(),
+            None if config.checksum_max_size > 0 && object.size > config.checksum_max_size => {
+                continue
+            }
+            None => match sha256(mount.store.as_ref(), &object.key).await? {
+                Some(checksum) => checksum,
+                None => continue,
+            },
+        };
+        if name.contains(['\\', '\n']) {
+            let name = name.replace('\\', "\\\\").replace('\n', "\\n");
+            manifest.push_str(&format!("\\{checksum}  {name}\n"));
+        } else {
+            manifest.push_str(&format!("{checksum}  {name}\n"));
+        }
+    }
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/plain; charset=utf-8")?;
+    Ok(worker::Response::ok(manifest)?.with_headers(headers))
+}
+
+async fn sha256(
+    store: &dyn crate::store::ObjectStore,
+    key: &str,
+) -> worker::Result<Option<String>> {
+    let Some((_, body)) = store.get(key).await? else {
+        return Ok(None);
+    };
+    let mut hasher = sha2::Sha256::new();
+    match body {
+        crate::store::Body::Bytes(bytes) => hasher.update(bytes),
+        body => {
+            let mut stream = body.into_response()?.stream()?;
+            while let Some(chunk) = stream.next().await {
+                hasher.update(chunk?);
+            }
+        }
+    }
+    Ok(Some(crate::hex(&hasher.finalize())))
+}
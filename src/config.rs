@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+
+const OVERLAY_BINDING: &str = "CONFIG";
+const OVERLAY_CACHE_TTL: u64 = 60;
+const DEFAULT_OVERLAY_KEY: &str = "config";
+const DEFAULT_CHECKSUM_MAX_SIZE: u64 = 256 * 1024 * 1024;
+const DEFAULT_CORS_ALLOWED_METHODS: &str = "GET, HEAD, OPTIONS";
+const DEFAULT_CORS_MAX_AGE: u64 = 86400;
+const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data:; script-src 'self' 'unsafe-inline' https://challenges.cloudflare.com; frame-src https://challenges.cloudflare.com; object-src 'none'; base-uri 'self'; frame-ancestors 'none'";
+const DEFAULT_DU_CACHE_TTL: u64 = 3600;
+const DEFAULT_QUOTA_DEPTH: usize = 1;
+const DEFAULT_QUOTA_WINDOW: u64 = 86400;
+const DEFAULT_RATE_LIMIT_RETRY_AFTER: u64 = 60;
+const DEFAULT_REFERRER_POLICY: &str = "strict-origin-when-cross-origin";
+const DEFAULT_SHARE_TTL: u64 = 86400;
+const DEFAULT_SITEMAP_CACHE_TTL: u64 = 3600;
+const DEFAULT_SIZE_DECIMAL_PLACES: usize = 2;
+const DEFAULT_STRICT_TRANSPORT_SECURITY: &str = "max-age=31536000";
+const DEFAULT_THUMBNAIL_SIZE: u32 = 200;
+const DEFAULT_TORRENT_MAX_SIZE: u64 = 256 * 1024 * 1024;
+const DEFAULT_TRASH_RETENTION: u64 = 30 * 86400;
+const DEFAULT_LISTING_EXCLUDE: &str = ".*,_headers,_redirects";
+const DEFAULT_TURNSTILE_PASS_TTL: u64 = 3600;
+
+pub struct Config {
+    pub allow_delete: bool,
+    pub auth_users: Option<HashMap<String, String>>,
+    pub bucket_hosts: HashMap<String, crate::mounts::Host>,
+    pub bucket_mounts: HashMap<String, String>,
+    pub cache_control_default: Option<String>,
+    pub cache_control_extensions: HashMap<String, String>,
+    pub case_insensitive_fallback: bool,
+    pub checksum_max_size: u64,
+    pub clean_urls: bool,
+    pub cloudflare_api_token: Option<String>,
+    pub cloudflare_zone_id: Option<String>,
+    pub content_security_policy: Option<String>,
+    pub content_type_options: Option<String>,
+    pub cors_allowed_headers: Option<String>,
+    pub cors_allowed_methods: String,
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_max_age: u64,
+    pub date_format: Option<String>,
+    pub du_cache_ttl: u64,
+    pub embed_frame_ancestors: String,
+    pub error_page: String,
+    pub error_webhook_url: Option<url::Url>,
+    pub font_url: Option<String>,
+    pub graphql: bool,
+    pub headers_file: Option<String>,
+    pub highlights: Option<usize>,
+    pub hotlink_allowed_hosts: Option<Vec<String>>,
+    pub hotlink_redirect_url: Option<String>,
+    pub language: String,
+    pub listing_cache_origins: Vec<String>,
+    pub listing_cache_ttl: Option<u64>,
+    pub listing_exclude: Vec<String>,
+    pub listing_load_more: bool,
+    pub locale: Option<String>,
+    pub log_level: crate::logging::Level,
+    pub metadata_columns: Vec<String>,
+    pub mime_types: HashMap<String, String>,
+    pub mtls_required: bool,
+    pub noindex_status: u16,
+    pub not_found_page: String,
+    pub precompressed: bool,
+    pub preview_exif: bool,
+    pub preview_exif_gps: bool,
+    pub quota_bytes: Option<u64>,
+    pub quota_depth: usize,
+    pub quota_status: u16,
+    pub quota_window: u64,
+    pub rate_limit_retry_after: u64,
+    pub redirects_file: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub relative_times: bool,
+    pub robots_tag_files: Option<String>,
+    pub robots_tag_listings: Option<String>,
+    pub robots_txt: Option<String>,
+    pub root_prefix: String,
+    pub sentry_dsn: Option<url::Url>,
+    pub share_signing_key: Option<String>,
+    pub share_ttl: u64,
+    pub show_checksums: bool,
+    pub show_directory_sizes: bool,
+    pub show_downloads: bool,
+    pub sitemap_cache_ttl: u64,
+    pub size_decimal_places: usize,
+    pub size_units: crate::sizes::Units,
+    pub sort_by_size: bool,
+    pub sort_case_insensitive: bool,
+    pub sort_directories_first: bool,
+    pub sort_natural: bool,
+    pub spa_fallback: bool,
+    pub strict_transport_security: Option<String>,
+    pub throttle_anonymous_only: bool,
+    pub throttle_bytes_per_sec: Option<u64>,
+    pub thumbnail_height: u32,
+    pub thumbnail_width: u32,
+    pub thumbnails: bool,
+    pub timezone: chrono_tz::Tz,
+    pub torrent_max_size: u64,
+    pub torrent_trackers: Vec<String>,
+    pub translations: HashMap<String, HashMap<String, String>>,
+    pub trash_prefix: Option<String>,
+    pub trash_retention: u64,
+    pub turnstile_pass_ttl: u64,
+    pub turnstile_secret_key: Option<String>,
+    pub turnstile_site_key: Option<String>,
+    pub webhook_events: Vec<String>,
+    pub webhook_url: Option<url::Url>,
+}
+
+impl Config {
+    pub async fn load(environment: &worker::Env) -> worker::Result<Self> {
+        let overlay = overlay(environment).await?;
+        Self::parse(|name| {
+            overlay
+                .get(name)
+                .cloned()
+                .or_else(|| var(environment, name))
+        })
+    }
+
+    fn parse(var: impl Fn(&str) -> Option<String>) -> worker::Result<Self> {
+        let flag = |name: &str| {
+            var(name).is_some_and(|value| matches!(value.as_str(), "1" | "true" | "yes"))
+        };
+        let list = |name: &str| {
+            var(name).map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        Ok(Self {
+            allow_delete: flag("ALLOW_DELETE"),
+            auth_users: json(&var, "AUTH_USERS")?,
+            bucket_hosts: json(&var, "BUCKET_HOSTS")?.unwrap_or_default(),
+            bucket_mounts: json(&var, "BUCKET_MOUNTS")?.unwrap_or_default(),
+            cache_control_default: var("CACHE_CONTROL_DEFAULT"),
+            cache_control_extensions: json(&var, "CACHE_CONTROL_EXTENSIONS")?.unwrap_or_default(),
+            case_insensitive_fallback: flag("CASE_INSENSITIVE_FALLBACK"),
+            checksum_max_size: parsed(&var, "CHECKSUM_MAX_SIZE")?
+                .unwrap_or(DEFAULT_CHECKSUM_MAX_SIZE),
+            clean_urls: flag("CLEAN_URLS"),
+            cloudflare_api_token: var("CLOUDFLARE_API_TOKEN"),
+            cloudflare_zone_id: var("CLOUDFLARE_ZONE_ID"),
+            content_security_policy: header(
+                &var,
+                "CONTENT_SECURITY_POLICY",
+                DEFAULT_CONTENT_SECURITY_POLICY,
+            ),
+            content_type_options: header(&var, "X_CONTENT_TYPE_OPTIONS", "nosniff"),
+            cors_allowed_headers: var("CORS_ALLOWED_HEADERS"),
+            cors_allowed_methods: var("CORS_ALLOWED_METHODS")
+                .unwrap_or_else(|| DEFAULT_CORS_ALLOWED_METHODS.into()),
+            cors_allowed_origins: list("CORS_ALLOWED_ORIGINS").unwrap_or_default(),
+            cors_max_age: parsed(&var, "CORS_MAX_AGE")?.unwrap_or(DEFAULT_CORS_MAX_AGE),
+            date_format: var("DATE_FORMAT")
+                .map(|format| strftime("DATE_FORMAT", format))
+                .transpose()?,
+            du_cache_ttl: parsed(&var, "DU_CACHE_TTL")?.unwrap_or(DEFAULT_DU_CACHE_TTL),
+            embed_frame_ancestors: var("EMBED_FRAME_ANCESTORS").unwrap_or_else(|| "*".into()),
+            error_page: var("ERROR_PAGE").unwrap_or_else(|| "error.html".into()),
+            error_webhook_url: parsed(&var, "ERROR_WEBHOOK_URL")?,
+            font_url: var("FONT_URL"),
+            graphql: flag("GRAPHQL"),
+            headers_file: var("HEADERS_FILE"),
+            highlights: parsed(&var, "HIGHLIGHTS")?.filter(|limit| *limit > 0),
+            hotlink_allowed_hosts: list("HOTLINK_ALLOWED_HOSTS"),
+            hotlink_redirect_url: var("HOTLINK_REDIRECT_URL"),
+            language: var("LOCALE").unwrap_or_else(|| crate::i18n::DEFAULT_LANGUAGE.into()),
+            listing_cache_origins: list("LISTING_CACHE_ORIGINS")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|origin| origin.trim_end_matches('/').to_owned())
+                .collect(),
+            listing_cache_ttl: parsed(&var, "LISTING_CACHE_TTL")?.filter(|ttl| *ttl > 0),
+            listing_exclude: list("LISTING_EXCLUDE").unwrap_or_else(|| {
+                DEFAULT_LISTING_EXCLUDE
+                    .split(',')
+                    .map(str::to_owned)
+                    .collect()
+            }),
+            listing_load_more: flag("LISTING_LOAD_MORE"),
+            locale: var("LOCALE"),
+            log_level: parsed(&var, "LOG_LEVEL")?.unwrap_or(crate::logging::Level::Info),
+            metadata_columns: list("METADATA_COLUMNS").unwrap_or_default(),
+            mime_types: json(&var, "MIME_TYPES")?.unwrap_or_default(),
+            mtls_required: flag("MTLS_REQUIRED"),
+            noindex_status: match parsed(&var, "NOINDEX_STATUS")? {
+                None => 404,
+                Some(status @ (403 | 404)) => status,
+                Some(status) => return Err(invalid("NOINDEX_STATUS", status)),
+            },
+            not_found_page: var("NOT_FOUND_PAGE").unwrap_or_else(|| "404.html".into()),
+            precompressed: flag("PRECOMPRESSED"),
+            preview_exif: !matches!(var("PREVIEW_EXIF").as_deref(), Some("0" | "false" | "no")),
+            preview_exif_gps: flag("PREVIEW_EXIF_GPS"),
+            quota_bytes: parsed(&var, "QUOTA_BYTES")?,
+            quota_depth: parsed(&var, "QUOTA_DEPTH")?.unwrap_or(DEFAULT_QUOTA_DEPTH),
+            quota_status: match parsed(&var, "QUOTA_STATUS")? {
+                None => 429,
+                Some(status @ (403 | 429)) => status,
+                Some(status) => return Err(invalid("QUOTA_STATUS", status)),
+            },
+            quota_window: parsed(&var, "QUOTA_WINDOW")?.unwrap_or(DEFAULT_QUOTA_WINDOW),
+            rate_limit_retry_after: parsed(&var, "RATE_LIMIT_RETRY_AFTER")?
+                .unwrap_or(DEFAULT_RATE_LIMIT_RETRY_AFTER),
+            redirects_file: var("REDIRECTS_FILE"),
+            referrer_policy: header(&var, "REFERRER_POLICY", DEFAULT_REFERRER_POLICY),
+            relative_times: flag("RELATIVE_TIMES"),
+            robots_tag_files: var("ROBOTS_TAG_FILES"),
+            robots_tag_listings: var("ROBOTS_TAG_LISTINGS"),
+            robots_txt: var("ROBOTS_TXT"),
+            root_prefix: var("ROOT_PREFIX").unwrap_or_default(),
+            sentry_dsn: parsed(&var, "SENTRY_DSN")?,
+            share_signing_key: var("SHARE_SIGNING_KEY"),
+            share_ttl: parsed(&var, "SHARE_TTL")?.unwrap_or(DEFAULT_SHARE_TTL),
+            show_checksums: flag("SHOW_CHECKSUMS"),
+            show_directory_sizes: flag("SHOW_DIRECTORY_SIZES"),
+            show_downloads: flag("SHOW_DOWNLOADS"),
+            sitemap_cache_ttl: parsed(&var, "SITEMAP_CACHE_TTL")?
+                .unwrap_or(DEFAULT_SITEMAP_CACHE_TTL),
+            size_decimal_places: parsed(&var, "SIZE_DECIMAL_PLACES")?
+                .unwrap_or(DEFAULT_SIZE_DECIMAL_PLACES),
+            size_units: parsed(&var, "SIZE_UNITS")?.unwrap_or(crate::sizes::Units::Decimal),
+            sort_by_size: flag("SORT_BY_SIZE"),
+            sort_case_insensitive: flag("SORT_CASE_INSENSITIVE"),
+            sort_directories_first: !matches!(
+                var("SORT_DIRECTORIES_FIRST").as_deref(),
+                Some("0" | "false" | "no")
+            ),
+            sort_natural: flag("SORT_NATURAL"),
+            spa_fallback: flag("SPA_FALLBACK"),
+            strict_transport_security: header(
+                &var,
+                "STRICT_TRANSPORT_SECURITY",
+                DEFAULT_STRICT_TRANSPORT_SECURITY,
+            ),
+            throttle_anonymous_only: flag("THROTTLE_ANONYMOUS_ONLY"),
+            throttle_bytes_per_sec: parsed(&var, "THROTTLE_BYTES_PER_SEC")?
+                .filter(|rate| *rate > 0),
+            thumbnail_height: parsed(&var, "THUMBNAIL_HEIGHT")?.unwrap_or(DEFAULT_THUMBNAIL_SIZE),
+            thumbnail_width: parsed(&var, "THUMBNAIL_WIDTH")?.unwrap_or(DEFAULT_THUMBNAIL_SIZE),
+            thumbnails: flag("THUMBNAILS"),
+            timezone: parsed(&var, "TIMEZONE")?.unwrap_or(chrono_tz::UTC),
+            torrent_max_size: parsed(&var, "TORRENT_MAX_SIZE")?.unwrap_or(DEFAULT_TORRENT_MAX_SIZE),
+            torrent_trackers: list("TORRENT_TRACKERS").unwrap_or_default(),
+            translations: translations(&var)?,
+            trash_prefix: var("TRASH_PREFIX")
+                .map(|prefix| format!("{}/", prefix.trim_matches('/')))
+                .filter(|prefix| prefix != "/"),
+            trash_retention: parsed(&var, "TRASH_RETENTION")?.unwrap_or(DEFAULT_TRASH_RETENTION),
+            turnstile_pass_ttl: parsed(&var, "TURNSTILE_PASS_TTL")?
+                .unwrap_or(DEFAULT_TURNSTILE_PASS_TTL),
+            turnstile_secret_key: var("TURNSTILE_SECRET_KEY"),
+            turnstile_site_key: var("TURNSTILE_SITE_KEY"),
+            webhook_events: list("WEBHOOK_EVENTS").unwrap_or_else(|| vec!["*".into()]),
+            webhook_url: parsed(&var, "WEBHOOK_URL")?,
+        })
+    }
+}
+
+#[cfg(test)]
+impl Config {
+    pub fn from_vars(vars: &[(&str, &str)]) -> Self {
+        Self::parse(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+        .expect("the test settings must be valid")
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::parse(|_| None).expect("the defaults must be valid")
+    }
+}
+
+async fn overlay(environment: &worker::Env) -> worker::Result<HashMap<String, String>> {
+    let Ok(store) = environment.kv(OVERLAY_BINDING) else {
+        return Ok(HashMap::new());
+    };
+    let key = var(environment, "CONFIG_KEY").unwrap_or_else(|| DEFAULT_OVERLAY_KEY.into());
+    let document: HashMap<String, serde_json::Value> = store
+        .get(&key)
+        .cache_ttl(OVERLAY_CACHE_TTL)
+        .json()
+        .await?
+        .unwrap_or_default();
+    Ok(document
+        .into_iter()
+        .filter_map(|(name, value)| match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(value) => Some((name, value)),
+            value => Some((name, value.to_string())),
+        })
+        .filter(|(_, value)| !value.is_empty())
+        .collect())
+}
+
+fn var(environment: &worker::Env, name: &str) -> Option<String> {
+    environment
+        .var(name)
+        .ok()
+        .map(|var| var.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn header(var: &impl Fn(&str) -> Option<String>, name: &str, default: &str) -> Option<String> {
+    match var(name) {
+        Some(value) if value == "off" => None,
+        Some(value) => Some(value),
+        None => Some(default.into()),
+    }
+}
+
+fn json<T: serde::de::DeserializeOwned>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> worker::Result<Option<T>> {
+    var(name)
+        .map(|value| serde_json::from_str(&value))
+        .transpose()
+        .map_err(|err| invalid(name, err))
+}
+
+fn parsed<T: std::str::FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> worker::Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    var(name)
+        .map(|value| value.trim().parse())
+        .transpose()
+        .map_err(|err| invalid(name, err))
+}
+
+fn strftime(name: &str, format: String) -> worker::Result<String> {
+    match chrono::format::StrftimeItems::new(&format)
+        .any(|item| item == chrono::format::Item::Error)
+    {
+        true => Err(invalid(name, format)),
+        false => Ok(format),
+    }
+}
+
+fn translations(
+    var: &impl Fn(&str) -> Option<String>,
+) -> worker::Result<HashMap<String, HashMap<String, String>>> {
+    let translations: HashMap<String, HashMap<String, String>> =
+        json(var, "TRANSLATIONS")?.unwrap_or_default();
+    for texts in translations.values() {
+        if let Some(format) = texts.get(crate::dates::FORMAT) {
+            strftime("TRANSLATIONS", format.clone())?;
+        }
+    }
+    Ok(translations)
+}
+
+fn invalid(name: &str, err: impl std::fmt::Display) -> worker::Error {
+    worker::Error::RustError(format!("invalid `{name}`: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_date_formats() {
+        assert!(Config::parse(|name| (name == "DATE_FORMAT").then(|| "%Y-%m-%d".into())).is_ok());
+        assert!(Config::parse(|name| (name == "DATE_FORMAT").then(|| "%Y-%!".into())).is_err());
+        let translations = |format: &str| {
+            let translations = serde_json::json!({ "de": { crate::dates::FORMAT: format } });
+            Config::parse(|name| (name == "TRANSLATIONS").then(|| translations.to_string()))
+        };
+        assert!(translations("%d.%m.%Y").is_ok());
+        assert!(translations("%d.%m.%Y %Q").is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use unicode_normalization::UnicodeNormalization;
+
+pub fn apply(url: &url::Url, key: &str, headers: &mut worker::Headers) -> worker::Result<()> {
+    let filename = key.rsplit('/').next().unwrap_or(key);
+    match url.query_pairs().find(|(name, _)| name == "download") {
+        Some((_, download_name)) => {
+            let filename = if download_name.is_empty() {
+                filename
+            } else {
+                &download_name
+            };
+            headers.set("content-disposition", &header("attachment", filename))?;
+        }
+        None if !headers.has("content-disposition")? && !filename.is_empty() => {
+            headers.set("content-disposition", &header("inline", filename))?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+pub fn header(disposition: &str, filename: &str) -> String {
+    let filename: String = filename.nfc().collect();
+    let fallback: String = filename
+        .chars()
+        .map(|char| match char {
+            ' '..='~' if char != '"' && char != '\\' => char,
+            _ => '_',
+        })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'!'
+            | b'#'
+            | b'$'
+            | b'&'
+            | b'+'
+            | b'-'
+            | b'.'
+            | b'^'
+            | b'_'
+            | b'`'
+            | b'|'
+            | b'~' => char::from(byte).to_string(),
+            _ => format!("%{byte:02X}"),
+        })
+        .collect();
+    format!("{disposition}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
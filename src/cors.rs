@@ -0,0 +1,75 @@
+pub fn preflight(
+    request: &worker::Request,
+    config: &crate::config::Config,
+) -> worker::Result<Option<worker::Response>> {
+    if request.method() != worker::Method::Options
+        || !request.headers().has("access-control-request-method")?
+    {
+        return Ok(None);
+    }
+    let Some(origin) = allowed_origin(request, config)? else {
+        return Ok(None);
+    };
+    let mut headers = worker::Headers::new();
+    headers.set("access-control-allow-origin", &origin)?;
+    headers.set("access-control-allow-methods", &config.cors_allowed_methods)?;
+    match &config.cors_allowed_headers {
+        Some(allowed_headers) => headers.set("access-control-allow-headers", allowed_headers)?,
+        None => {
+            if let Some(requested) = request.headers().get("access-control-request-headers")? {
+                headers.set("access-control-allow-headers", &requested)?;
+            }
+        }
+    }
+    headers.set("access-control-max-age", &config.cors_max_age.to_string())?;
+    headers.set("vary", "origin")?;
+    Ok(Some(
+        worker::Response::empty()?
+            .with_status(204)
+            .with_headers(headers),
+    ))
+}
+
+pub fn apply(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    response: worker::Response,
+) -> worker::Result<worker::Response> {
+    let Some(origin) = allowed_origin(request, config)? else {
+        return Ok(response);
+    };
+    let mut headers = response.headers().clone();
+    headers.set("access-control-allow-origin", &origin)?;
+    headers.set(
+        "access-control-expose-headers",
+        "content-length, content-range, etag, x-request-id",
+    )?;
+    if origin != "*" {
+        headers.append("vary", "origin")?;
+    }
+    Ok(response.with_headers(headers))
+}
+
+fn allowed_origin(
+    request: &worker::Request,
+    config: &crate::config::Config,
+) -> worker::Result<Option<String>> {
+    let Some(origin) = request.headers().get("origin")? else {
+        return Ok(None);
+    };
+    Ok(
+        if config
+            .cors_allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*")
+        {
+            Some("*".into())
+        } else {
+            config
+                .cors_allowed_origins
+                .iter()
+                .any(|allowed| allowed.trim_end_matches('/') == origin)
+                .then_some(origin)
+        },
+    )
+}
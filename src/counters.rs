@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use worker::{async_trait, js_sys, wasm_bindgen, wasm_bindgen_futures, worker_sys};
+
+const BINDING: &str = "COUNTERS";
+const NAME: &str = "downloads";
+
+#[worker::durable_object]
+pub struct Counters {
+    state: worker::State,
+}
+
+#[worker::durable_object]
+impl DurableObject for Counters {
+    fn new(state: worker::State, _environment: worker::Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, request: worker::Request) -> worker::Result<worker::Response> {
+        let url = request.url()?;
+        let parameter = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default()
+        };
+        let mut storage = self.state.storage();
+        if url.path() == "/increment" {
+            let key = storage_key(&parameter("path"));
+            let count = storage.get::<u64>(&key).await.unwrap_or_default() + 1;
+            storage.put(&key, count).await?;
+            return worker::Response::empty();
+        }
+
+        let prefix = parameter("prefix");
+        let entries = storage
+            .list_with_options(worker::ListOptions::new().prefix(&prefix))
+            .await?;
+        let mut counts = Vec::new();
+        entries.for_each(&mut |count, key| {
+            if let (Some(key), Some(count)) = (key.as_string(), count.as_f64()) {
+                counts.push((key.replace('\0', ""), count as u64));
+            }
+        });
+        worker::Response::from_json(&counts)
+    }
+}
+
+fn storage_key(path: &str) -> String {
+    let (directory, name) = match path.rfind('/') {
+        Some(index) => path.split_at(index + 1),
+        None => ("", path),
+    };
+    format!("{directory}\0{name}")
+}
+
+fn stub(environment: &worker::Env) -> Option<worker::Stub> {
+    let namespace = environment.durable_object(BINDING).ok()?;
+    namespace.id_from_name(NAME).ok()?.get_stub().ok()
+}
+
+fn counter(mount: &crate::mounts::Mount, key: &str) -> String {
+    format!("{}/{key}", mount.binding)
+}
+
+pub fn increment(
+    environment: &worker::Env,
+    context: &worker::Context,
+    mount: &crate::mounts::Mount,
+    key: &str,
+) {
+    let Some(stub) = stub(environment) else {
+        return;
+    };
+    let mut url = url::Url::parse("https://counters/increment").expect("must be valid");
+    url.query_pairs_mut()
+        .append_pair("path", &counter(mount, key));
+    context.wait_until(async move {
+        if let Err(err) = stub.fetch_with_str(url.as_str()).await {
+            worker::console_error!("failed to count a download: {err}");
+        }
+    });
+}
+
+pub async fn counts(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path_prefix: &str,
+    recursive: bool,
+) -> worker::Result<Option<HashMap<String, u64>>> {
+    if !config.show_downloads {
+        return Ok(None);
+    }
+    let Some(stub) = stub(environment) else {
+        return Ok(None);
+    };
+    let counter_prefix = counter(mount, &mount.key(path_prefix));
+    let prefix = match recursive {
+        true => counter_prefix.clone(),
+        false => format!("{counter_prefix}\0"),
+    };
+    Ok(Some(
+        list(&stub, &prefix)
+            .await?
+            .into_iter()
+            .filter_map(|(path, count)| {
+                Some((path.strip_prefix(&counter_prefix)?.to_owned(), count))
+            })
+            .collect(),
+    ))
+}
+
+pub async fn all(
+    environment: &worker::Env,
+    mount: &crate::mounts::Mount,
+) -> worker::Result<Vec<(String, u64)>> {
+    let Some(stub) = stub(environment) else {
+        return Ok(Vec::new());
+    };
+    let counter_prefix = counter(mount, &mount.key_prefix);
+    Ok(list(&stub, &counter_prefix)
+        .await?
+        .into_iter()
+        .filter_map(|(path, count)| {
+            let key = path.strip_prefix(&counter_prefix)?;
+            Some((format!("{}{key}", mount.path), count))
+        })
+        .collect())
+}
+
+async fn list(stub: &worker::Stub, prefix: &str) -> worker::Result<Vec<(String, u64)>> {
+    let mut url = url::Url::parse("https://counters/list").expect("must be valid");
+    url.query_pairs_mut().append_pair("prefix", prefix);
+    stub.fetch_with_str(url.as_str()).await?.json().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_counts_by_directory() {
+        assert_eq!(storage_key("a.txt"), "\0a.txt");
+        assert_eq!(storage_key("docs/a.txt"), "docs/\0a.txt");
+        assert_eq!(storage_key("docs/nested/a.txt"), "docs/nested/\0a.txt");
+        assert!(storage_key("docs/nested/a.txt").starts_with("docs/"));
+        assert!(!storage_key("docs/nested/a.txt").starts_with("docs/\0"));
+    }
+
+    #[test]
+    fn namespaces_counters_by_binding() {
+        let mount = |binding: &str, path: &str, key_prefix: &str| crate::mounts::Mount {
+            store: std::rc::Rc::new(crate::store::MemoryStore::new()),
+            binding: binding.to_owned(),
+            path: path.to_owned(),
+            key_prefix: key_prefix.to_owned(),
+        };
+        let root = mount("BUCKET", "", "public/");
+        let media = mount("BUCKET_MEDIA", "media/", "");
+        assert_eq!(
+            storage_key(&counter(&root, &root.key("docs/a.txt"))),
+            "BUCKET/public/docs/\0a.txt"
+        );
+        assert_eq!(
+            storage_key(&counter(&media, &media.key("media/docs/a.txt"))),
+            "BUCKET_MEDIA/docs/\0a.txt"
+        );
+    }
+}
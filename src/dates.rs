@@ -0,0 +1,68 @@
+pub const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Clone)]
+pub struct Dates {
+    timezone: chrono_tz::Tz,
+    format: Option<String>,
+    relative: bool,
+    now: chrono::DateTime<chrono::Utc>,
+    i18n: crate::i18n::Translator,
+}
+
+impl Dates {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            timezone: config.timezone,
+            format: config.date_format.clone(),
+            relative: config.relative_times,
+            now: chrono::Utc::now(),
+            i18n: crate::i18n::Translator::from_config(config),
+        }
+    }
+
+    pub fn absolute(&self, at: chrono::DateTime<chrono::Utc>) -> String {
+        let at = at.with_timezone(&self.timezone);
+        let format = self.i18n.text(FORMAT);
+        if let Some(format) = &self.format {
+            at.format(format).to_string()
+        } else if self.timezone == chrono_tz::UTC {
+            at.format(format).to_string()
+        } else {
+            at.format(&format!("{format} %Z")).to_string()
+        }
+    }
+
+    pub fn display(&self, at: chrono::DateTime<chrono::Utc>) -> String {
+        match self.relative {
+            true => format!("{} {}", self.relative(at), self.absolute(at)),
+            false => self.absolute(at),
+        }
+    }
+
+    pub fn relative(&self, at: chrono::DateTime<chrono::Utc>) -> String {
+        let seconds = (self.now - at).num_seconds().max(0);
+        let (count, one, many) = match seconds {
+            0..=59 => return self.i18n.text("just now").to_owned(),
+            60..=3599 => (seconds / 60, "1 minute ago", "{count} minutes ago"),
+            3600..=86_399 => (seconds / 3600, "1 hour ago", "{count} hours ago"),
+            86_400..=2_591_999 => (seconds / 86_400, "1 day ago", "{count} days ago"),
+            2_592_000..=31_535_999 => (seconds / 2_592_000, "1 month ago", "{count} months ago"),
+            _ => (seconds / 31_536_000, "1 year ago", "{count} years ago"),
+        };
+        self.i18n.count(one, many, count as u64)
+    }
+}
+
+markup::define! {
+    Timestamp<'a>(dates: &'a Dates, at: chrono::DateTime<chrono::Utc>) {
+        @if dates.relative {
+            time[datetime = at.to_rfc3339(), title = dates.absolute(*at)] {
+                @dates.relative(*at)
+            }
+        } else {
+            time[datetime = at.to_rfc3339()] {
+                @dates.absolute(*at)
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use futures_util::StreamExt;
+
+use crate::EntryType;
+
+pub const BINDING: &str = "DIRECTORY_SIZES";
+const KEY_PREFIX: &str = "du:";
+pub const MIN_CACHE_TTL: u64 = 60;
+const MAX_DIRECTORIES: usize = 100;
+const CONCURRENCY: usize = 6;
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct Usage {
+    pub size: u64,
+    pub count: u64,
+}
+
+#[derive(Clone)]
+pub struct DiskUsage {
+    cache: Option<worker::kv::KvStore>,
+    ttl: u64,
+    filter: crate::filter::EntryFilter,
+}
+
+impl DiskUsage {
+    pub fn from_config(environment: &worker::Env, config: &crate::config::Config) -> Self {
+        Self {
+            cache: environment.kv(BINDING).ok(),
+            ttl: config.du_cache_ttl.max(MIN_CACHE_TTL),
+            filter: crate::filter::EntryFilter::from_config(config, false),
+        }
+    }
+
+    pub async fn usage(&self, mount: &crate::mounts::Mount, path: &str) -> worker::Result<Usage> {
+        if let Some(usage) = self.cached(mount, path).await? {
+            return Ok(usage);
+        }
+        self.compute(mount, path).await
+    }
+
+    pub async fn cached(
+        &self,
+        mount: &crate::mounts::Mount,
+        path: &str,
+    ) -> worker::Result<Option<Usage>> {
+        match &self.cache {
+            Some(cache) => cache
+                .get(&name(mount, path))
+                .json()
+                .await
+                .map_err(Into::into),
+            None => Ok(None),
+        }
+    }
+
+    async fn compute(&self, mount: &crate::mounts::Mount, path: &str) -> worker::Result<Usage> {
+        let mut usage = Usage::default();
+        for (_, object) in crate::walk::files(mount, path, &self.filter).await? {
+            usage.size += object.size;
+            usage.count += 1;
+        }
+        if let Some(cache) = &self.cache {
+            cache
+                .put(&name(mount, path), usage)?
+                .expiration_ttl(self.ttl)
+                .execute()
+                .await?;
+        }
+        Ok(usage)
+    }
+
+    pub async fn directories(
+        &self,
+        context: Option<&worker::Context>,
+        mount: &crate::mounts::Mount,
+        path: &str,
+        entries: &[(EntryType, String)],
+    ) -> worker::Result<HashMap<String, Usage>> {
+        let key_prefix = mount.key(path);
+        let names = entries
+            .iter()
+            .filter(|(entry_type, _)| *entry_type == EntryType::Directory)
+            .map(|(_, key)| key.strip_prefix(key_prefix.as_str()).unwrap_or(key))
+            .take(MAX_DIRECTORIES)
+            .map(|name| (name.to_owned(), format!("{path}{name}")));
+        self.known(context, mount, names).await
+    }
+
+    pub async fn prefix(
+        &self,
+        context: &worker::Context,
+        mount: &crate::mounts::Mount,
+        path: &str,
+    ) -> worker::Result<Option<Usage>> {
+        let usages = self
+            .known(Some(context), mount, [(String::new(), path.to_owned())])
+            .await?;
+        Ok(usages.into_values().next())
+    }
+
+    async fn known(
+        &self,
+        context: Option<&worker::Context>,
+        mount: &crate::mounts::Mount,
+        paths: impl IntoIterator<Item = (String, String)>,
+    ) -> worker::Result<HashMap<String, Usage>> {
+        if self.cache.is_none() {
+            return Ok(HashMap::new());
+        }
+        let lookups = futures_util::stream::iter(paths)
+            .map(|(name, path)| async move {
+                let usage = self.cached(mount, &path).await;
+                (name, path, usage)
+            })
+            .buffer_unordered(CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        let mut usages = HashMap::new();
+        let mut missing = Vec::new();
+        for (name, path, usage) in lookups {
+            match usage? {
+                Some(usage) => {
+                    usages.insert(name, usage);
+                }
+                None => missing.push(path),
+            }
+        }
+        if let Some(context) = context.filter(|_| !missing.is_empty()) {
+            let disk_usage = self.clone();
+            let mount = mount.clone();
+            context.wait_until(async move {
+                let computed = futures_util::stream::iter(&missing)
+                    .map(|path| disk_usage.compute(&mount, path))
+                    .buffer_unordered(CONCURRENCY)
+                    .collect::<Vec<_>>()
+                    .await;
+                for err in computed.into_iter().filter_map(Result::err) {
+                    worker::console_error!("failed to compute a directory size: {err}");
+                }
+            });
+        }
+        Ok(usages)
+    }
+}
+
+fn name(mount: &crate::mounts::Mount, path: &str) -> String {
+    format!("{KEY_PREFIX}{}:{}", mount.binding, mount.key(path))
+}
+
+pub fn requested(url: &url::Url) -> bool {
+    url.query_pairs().any(|(name, _)| name == "du")
+}
+
+pub async fn handle(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let disk_usage = DiskUsage::from_config(environment, config);
+    let noindex = mount.key(&format!("{path}.noindex"));
+    if disk_usage.filter.excludes(path) || mount.store.head(&noindex).await?.is_some() {
+        return match config.noindex_status {
+            403 => {
+                crate::errors::response(environment, config, Some(mount), 403, "Forbidden").await
+            }
+            _ => crate::errors::response(environment, config, Some(mount), 404, "Not Found").await,
+        };
+    }
+    let usage = disk_usage.usage(mount, path).await?;
+    worker::Response::from_json(&serde_json::json!({
+        "path": if path.is_empty() { "/" } else { path },
+        "size": usage.size,
+        "count": usage.count,
+    }))
+}
+
+/// Notifications don't name the binding, so every bucket's entries for the
+/// changed prefixes are dropped.
+pub async fn invalidate(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    keys: &[String],
+) -> worker::Result<()> {
+    let Ok(cache) = environment.kv(BINDING) else {
+        return Ok(());
+    };
+    for binding in crate::mounts::bindings(config) {
+        for prefix in crate::prefixes(keys) {
+            cache
+                .delete(&format!("{KEY_PREFIX}{binding}:{prefix}"))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn counts_only_listed_files() {
+        let store = crate::store::MemoryStore::with_files(&[
+            ("docs/a.txt", "aaaa"),
+            ("docs/.secret", "ssssssss"),
+            ("docs/nested/b.txt", "bb"),
+            ("docs/private/.noindex", ""),
+            ("docs/private/c.txt", "cccccc"),
+            ("other.txt", "o"),
+        ]);
+        let mount = crate::mounts::Mount {
+            store: std::rc::Rc::new(store),
+            binding: "BUCKET".into(),
+            path: String::new(),
+            key_prefix: String::new(),
+        };
+        let disk_usage = DiskUsage {
+            cache: None,
+            ttl: MIN_CACHE_TTL,
+            filter: crate::filter::EntryFilter::from_config(&Default::default(), false),
+        };
+        let usage = disk_usage
+            .usage(&mount, "docs/")
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!((usage.size, usage.count), (6, 2));
+    }
+
+    #[test]
+    fn names_entries_by_binding() {
+        let mount = crate::mounts::Mount {
+            store: std::rc::Rc::new(crate::store::MemoryStore::default()),
+            binding: "MEDIA".into(),
+            path: "media/".into(),
+            key_prefix: "public/".into(),
+        };
+        assert_eq!(name(&mount, "media/docs/"), "du:MEDIA:public/docs/");
+    }
+}
@@ -0,0 +1,89 @@
+markup::define! {
+    ErrorPage<'a>(status: u16, message: &'a str, back: &'a str, font_url: Option<&'a str>) {
+        @markup::doctype()
+        html {
+            head {
+                meta[charset = "utf-8"] {}
+                title { @status " " @message }
+                style {
+                    @crate::fonts::face(*font_url)
+                    "html { font-family: 'Inconsolata', monospace; }"
+                    "body { padding: 1em; }"
+                    "* { margin: 0; padding: 0; }"
+                    "header { margin-bottom: 2em; }"
+                    "p { margin-left: 1em; }"
+                }
+            }
+            body {
+                header {
+                    h1 { @status " " @message }
+                }
+                p {
+                    a[href = "/"] { @back }
+                }
+            }
+        }
+    }
+}
+
+pub async fn for_request(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    status: u16,
+    message: &str,
+) -> worker::Result<worker::Response> {
+    let mount = crate::mounts::for_request(request, environment, config)?;
+    response(environment, config, mount.as_ref(), status, message).await
+}
+
+pub async fn response(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    mount: Option<&crate::mounts::Mount>,
+    status: u16,
+    message: &str,
+) -> worker::Result<worker::Response> {
+    let mut pages = Vec::new();
+    if status == 404 {
+        pages.push(&config.not_found_page);
+    }
+    pages.push(&config.error_page);
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/html")?;
+    let store = match mount {
+        Some(mount) => Some(mount.store.clone()),
+        None => crate::store::binding(environment, "BUCKET").ok(),
+    };
+    if let Some(store) = store {
+        for page in pages {
+            let key = match mount {
+                Some(mount) => mount.key(page),
+                None => page.clone(),
+            };
+            if let Some((object, body)) = store.get(&key).await? {
+                object.write_http_metadata(&mut headers)?;
+                return Ok(body
+                    .into_response()?
+                    .with_status(status)
+                    .with_headers(headers));
+            }
+        }
+    }
+
+    let i18n = crate::i18n::Translator::from_config(config);
+    crate::i18n::write_headers(config, &mut headers)?;
+    crate::security::page(config, &mut headers)?;
+    Ok(worker::Response::ok(
+        ErrorPage {
+            status,
+            message: i18n.text(message),
+            back: i18n.text("Back to /"),
+            font_url: config.font_url.as_deref(),
+        }
+        .to_string(),
+    )?
+    .with_status(status)
+    .with_headers(headers))
+}
@@ -0,0 +1,48 @@
+use sha2::Digest;
+
+use crate::EntryType;
+
+pub fn for_entries(entries: &[(EntryType, String)], variant: &str) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(variant.as_bytes());
+    hasher.update([0]);
+    for (entry_type, key) in entries {
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        if let EntryType::File { size, uploaded } = entry_type {
+            hasher.update(size.to_le_bytes());
+            hasher.update(uploaded.timestamp_millis().to_le_bytes());
+        }
+    }
+    format!("W/\"{}\"", crate::hex(&hasher.finalize()[..16]))
+}
+
+pub fn conditional(
+    request: &worker::Request,
+    response: worker::Response,
+) -> worker::Result<worker::Response> {
+    let Some(etag) = response.headers().get("etag")? else {
+        return Ok(response);
+    };
+    let Some(if_none_match) = request.headers().get("if-none-match")? else {
+        return Ok(response);
+    };
+
+    if if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || weak_eq(candidate, &etag))
+    {
+        let mut headers = worker::Headers::new();
+        headers.set("etag", &etag)?;
+        Ok(worker::Response::empty()?
+            .with_status(304)
+            .with_headers(headers))
+    } else {
+        Ok(response)
+    }
+}
+
+fn weak_eq(left: &str, right: &str) -> bool {
+    left.trim_start_matches("W/") == right.trim_start_matches("W/")
+}
@@ -0,0 +1,437 @@
+use futures_util::StreamExt;
+
+const READ_LIMIT: usize = 128 * 1024;
+
+pub struct Exif {
+    pub camera: Option<String>,
+    pub dimensions: Option<(u32, u32)>,
+    pub captured: Option<String>,
+    pub location: Option<(f64, f64)>,
+}
+
+pub async fn read(
+    config: &crate::config::Config,
+    store: &dyn crate::store::ObjectStore,
+    key: &str,
+) -> worker::Result<Option<Exif>> {
+    if !config.preview_exif {
+        return Ok(None);
+    }
+    let Some((_, body)) = store.get(key).await? else {
+        return Ok(None);
+    };
+    let data = match body {
+        crate::store::Body::Bytes(mut bytes) => {
+            bytes.truncate(READ_LIMIT);
+            bytes
+        }
+        body => {
+            let mut data = Vec::new();
+            let mut stream = body.into_response()?.stream()?;
+            while let Some(chunk) = stream.next().await {
+                data.extend(chunk?);
+                if data.len() >= READ_LIMIT {
+                    break;
+                }
+            }
+            data
+        }
+    };
+    let mut exif = parse(&data);
+    if !config.preview_exif_gps {
+        if let Some(exif) = &mut exif {
+            exif.location = None;
+        }
+    }
+    Ok(exif)
+}
+
+fn parse(data: &[u8]) -> Option<Exif> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut exif = Exif {
+        camera: None,
+        dimensions: None,
+        captured: None,
+        location: None,
+    };
+    let mut offset = 2;
+    while let (Some(0xFF), Some(&marker)) = (data.get(offset), data.get(offset + 1)) {
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let Some(length) = data.get(offset + 2..offset + 4) else {
+            break;
+        };
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        let Some(segment) = data.get(offset + 4..offset + 2 + length) else {
+            break;
+        };
+        match marker {
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                if let Some(tiff) = Tiff::new(&segment[6..]) {
+                    tiff.read(&mut exif);
+                }
+            }
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) && exif.dimensions.is_none() => {
+                if let Some(&[_, height_high, height_low, width_high, width_low]) = segment.get(..5)
+                {
+                    let height = u16::from_be_bytes([height_high, height_low]);
+                    let width = u16::from_be_bytes([width_high, width_low]);
+                    exif.dimensions = Some((width.into(), height.into()));
+                }
+            }
+            _ => {}
+        }
+        offset += 2 + length;
+    }
+    (exif.camera.is_some()
+        || exif.dimensions.is_some()
+        || exif.captured.is_some()
+        || exif.location.is_some())
+    .then_some(exif)
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    offset: usize,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            little_endian,
+        })
+    }
+
+    fn read(&self, exif: &mut Exif) -> Option<()> {
+        let ifd = self.entries(self.u32(4)? as usize)?;
+        let ascii = |tag| {
+            ifd.iter()
+                .find(|entry| entry.tag == tag)
+                .and_then(|entry| self.ascii(entry))
+        };
+        exif.camera = match (ascii(0x010F), ascii(0x0110)) {
+            (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+            (Some(make), Some(model)) => Some(format!("{make} {model}")),
+            (make, model) => make.or(model),
+        };
+        exif.captured = ascii(0x0132);
+
+        let pointer = |tag| {
+            ifd.iter()
+                .find(|entry| entry.tag == tag)
+                .and_then(|entry| self.number(entry))
+        };
+        if let Some(sub_ifd) = pointer(0x8769).and_then(|offset| self.entries(offset as usize)) {
+            let find = |tag| sub_ifd.iter().find(|entry| entry.tag == tag);
+            if let Some(captured) = find(0x9003).and_then(|entry| self.ascii(entry)) {
+                exif.captured = Some(captured);
+            }
+            let width = find(0xA002).and_then(|entry| self.number(entry));
+            let height = find(0xA003).and_then(|entry| self.number(entry));
+            if let (Some(width), Some(height)) = (width, height) {
+                exif.dimensions = Some((width, height));
+            }
+        }
+        if let Some(gps) = pointer(0x8825).and_then(|offset| self.entries(offset as usize)) {
+            let find = |tag| gps.iter().find(|entry| entry.tag == tag);
+            let coordinate = |reference, value, negative| {
+                let degrees = self.rationals(find(value)?)?;
+                let sign = if self.ascii(find(reference)?)? == negative {
+                    -1.0
+                } else {
+                    1.0
+                };
+                Some(sign * (degrees.first()? + degrees.get(1)? / 60.0 + degrees.get(2)? / 3600.0))
+            };
+            if let (Some(latitude), Some(longitude)) =
+                (coordinate(1, 2, "S"), coordinate(3, 4, "W"))
+            {
+                exif.location = Some((latitude, longitude));
+            }
+        }
+        Some(())
+    }
+
+    fn entries(&self, offset: usize) -> Option<Vec<Entry>> {
+        let count = self.u16(offset)? as usize;
+        let entries = (0..count)
+            .map_while(|index| {
+                let position = offset + 2 + index * 12;
+                let kind = self.u16(position + 2)?;
+                let count = self.u32(position + 4)? as usize;
+                let size = match kind {
+                    3 => 2,
+                    4 | 9 => 4,
+                    5 | 10 => 8,
+                    _ => 1,
+                } * count;
+                Some(Entry {
+                    tag: self.u16(position)?,
+                    kind,
+                    count,
+                    offset: if size <= 4 {
+                        position + 8
+                    } else {
+                        self.u32(position + 8)? as usize
+                    },
+                })
+            })
+            .collect();
+        Some(entries)
+    }
+
+    fn ascii(&self, entry: &Entry) -> Option<String> {
+        if entry.kind != 2 {
+            return None;
+        }
+        let bytes = self.data.get(entry.offset..entry.offset + entry.count)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_owned())
+    }
+
+    fn number(&self, entry: &Entry) -> Option<u32> {
+        match entry.kind {
+            3 => self.u16(entry.offset).map(u32::from),
+            4 => self.u32(entry.offset),
+            _ => None,
+        }
+    }
+
+    fn rationals(&self, entry: &Entry) -> Option<Vec<f64>> {
+        if entry.kind != 5 {
+            return None;
+        }
+        (0..entry.count)
+            .map(|index| {
+                let numerator = self.u32(entry.offset + index * 8)?;
+                let denominator = self.u32(entry.offset + index * 8 + 4)?;
+                (denominator != 0).then(|| f64::from(numerator) / f64::from(denominator))
+            })
+            .collect()
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = [*self.data.get(offset)?, *self.data.get(offset + 1)?];
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Value {
+        Ascii(&'static str),
+        Short(u16),
+        Ifd(usize),
+        Rationals(&'static [(u32, u32)]),
+    }
+
+    fn tiff(little_endian: bool, ifds: &[&[(u16, Value)]]) -> Vec<u8> {
+        let u16 = |value: u16| match little_endian {
+            true => value.to_le_bytes(),
+            false => value.to_be_bytes(),
+        };
+        let u32 = |value: u32| match little_endian {
+            true => value.to_le_bytes(),
+            false => value.to_be_bytes(),
+        };
+        let mut offsets = vec![8];
+        for ifd in ifds {
+            offsets.push(offsets.last().unwrap() + 2 + 12 * ifd.len() + 4);
+        }
+        let mut data = Vec::new();
+        let mut values = Vec::new();
+        data.extend(if little_endian { b"II" } else { b"MM" });
+        data.extend(u16(42));
+        data.extend(u32(8));
+        for ifd in ifds {
+            data.extend(u16(ifd.len() as u16));
+            for (tag, value) in ifd.iter() {
+                let (kind, count, bytes) = match value {
+                    Value::Ascii(text) => (2, text.len() + 1, [text.as_bytes(), b"\0"].concat()),
+                    Value::Short(value) => (3, 1, [&u16(*value)[..], &[0, 0]].concat()),
+                    Value::Ifd(index) => (4, 1, u32(offsets[*index] as u32).to_vec()),
+                    Value::Rationals(rationals) => {
+                        let bytes = rationals
+                            .iter()
+                            .flat_map(|(numerator, denominator)| {
+                                [u32(*numerator), u32(*denominator)]
+                            })
+                            .flatten()
+                            .collect();
+                        (5, rationals.len(), bytes)
+                    }
+                };
+                data.extend(u16(*tag));
+                data.extend(u16(kind));
+                data.extend(u32(count as u32));
+                if bytes.len() <= 4 {
+                    data.extend(&bytes);
+                    data.extend(vec![0; 4 - bytes.len()]);
+                } else {
+                    data.extend(u32((offsets[ifds.len()] + values.len()) as u32));
+                    values.extend(bytes);
+                }
+            }
+            data.extend(u32(0));
+        }
+        data.extend(values);
+        data
+    }
+
+    fn jpeg(segments: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        for (marker, segment) in segments {
+            data.extend([0xFF, *marker]);
+            data.extend(((segment.len() + 2) as u16).to_be_bytes());
+            data.extend(segment);
+        }
+        data.extend([0xFF, 0xDA, 0, 2]);
+        data
+    }
+
+    fn app1(tiff: Vec<u8>) -> (u8, Vec<u8>) {
+        (0xE1, [&b"Exif\0\0"[..], &tiff].concat())
+    }
+
+    fn sof(width: u16, height: u16) -> (u8, Vec<u8>) {
+        let mut segment = vec![8];
+        segment.extend(height.to_be_bytes());
+        segment.extend(width.to_be_bytes());
+        segment.extend([3, 1, 0x22, 0]);
+        (0xC0, segment)
+    }
+
+    fn photo(little_endian: bool, latitude: &'static str, longitude: &'static str) -> Vec<u8> {
+        tiff(
+            little_endian,
+            &[
+                &[
+                    (0x010F, Value::Ascii("Canon")),
+                    (0x0110, Value::Ascii("Canon EOS R5")),
+                    (0x0132, Value::Ascii("2024:01:01 00:00:00")),
+                    (0x8769, Value::Ifd(1)),
+                    (0x8825, Value::Ifd(2)),
+                ],
+                &[
+                    (0x9003, Value::Ascii("2023:12:31 23:59:59")),
+                    (0xA002, Value::Short(6000)),
+                    (0xA003, Value::Short(4000)),
+                ],
+                &[
+                    (0x0001, Value::Ascii(latitude)),
+                    (0x0002, Value::Rationals(&[(48, 1), (51, 1), (2988, 100)])),
+                    (0x0003, Value::Ascii(longitude)),
+                    (0x0004, Value::Rationals(&[(2, 1), (17, 1), (4020, 100)])),
+                ],
+            ],
+        )
+    }
+
+    fn assert_photo(exif: &Exif, latitude_sign: f64, longitude_sign: f64) {
+        assert_eq!(exif.camera.as_deref(), Some("Canon EOS R5"));
+        assert_eq!(exif.captured.as_deref(), Some("2023:12:31 23:59:59"));
+        assert_eq!(exif.dimensions, Some((6000, 4000)));
+        let (latitude, longitude) = exif.location.unwrap();
+        assert!((latitude - latitude_sign * 48.8583).abs() < 1e-4);
+        assert!((longitude - longitude_sign * 2.2945).abs() < 1e-4);
+    }
+
+    #[test]
+    fn reads_little_endian_exif() {
+        let exif = parse(&jpeg(&[app1(photo(true, "N", "E"))])).unwrap();
+        assert_photo(&exif, 1.0, 1.0);
+    }
+
+    #[test]
+    fn reads_big_endian_exif() {
+        let exif = parse(&jpeg(&[app1(photo(false, "N", "E"))])).unwrap();
+        assert_photo(&exif, 1.0, 1.0);
+    }
+
+    #[test]
+    fn signs_southern_and_western_coordinates() {
+        let exif = parse(&jpeg(&[app1(photo(true, "S", "W"))])).unwrap();
+        assert_photo(&exif, -1.0, -1.0);
+        let exif = parse(&jpeg(&[app1(photo(false, "S", "E"))])).unwrap();
+        assert_photo(&exif, -1.0, 1.0);
+    }
+
+    #[test]
+    fn keeps_what_precedes_a_truncated_ifd() {
+        let mut tiff = tiff(
+            true,
+            &[
+                &[(0x8769, Value::Ifd(1))],
+                &[
+                    (0xA002, Value::Short(6000)),
+                    (0xA003, Value::Short(4000)),
+                    (0x9003, Value::Ascii("2023:12:31 23:59:59")),
+                ],
+            ],
+        );
+        let sub_ifd = 8 + 2 + 12 + 4;
+        tiff.truncate(sub_ifd + 2 + 12 * 2 + 6);
+        let exif = parse(&jpeg(&[app1(tiff)])).unwrap();
+        assert_eq!(exif.dimensions, Some((6000, 4000)));
+        assert_eq!(exif.captured, None);
+
+        let tiff = photo(true, "N", "E");
+        let exif = parse(&jpeg(&[
+            app1(tiff[..8 + 2 + 12 * 2].to_vec()),
+            sof(640, 480),
+        ]))
+        .unwrap();
+        assert_eq!(exif.camera, None);
+        assert_eq!(exif.dimensions, Some((640, 480)));
+    }
+
+    #[test]
+    fn skips_empty_segments() {
+        let exif = parse(&jpeg(&[(0xE0, Vec::new()), app1(photo(true, "N", "E"))])).unwrap();
+        assert_photo(&exif, 1.0, 1.0);
+        let exif = parse(&jpeg(&[(0xC0, Vec::new()), sof(640, 480)])).unwrap();
+        assert_eq!(exif.dimensions, Some((640, 480)));
+    }
+
+    #[test]
+    fn stops_at_zero_length_segments() {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0, 0];
+        data.extend(jpeg(&[sof(640, 480)]).split_off(2));
+        assert!(parse(&data).is_none());
+        assert!(parse(&[0xFF, 0xD8, 0xFF, 0xE1, 0, 1]).is_none());
+        assert!(parse(&[0xFF, 0xD8, 0xFF, 0xE1]).is_none());
+        assert!(parse(b"not a jpeg").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+pub const PATH: &str = "feed.xml";
+
+const LIMIT: usize = 50;
+
+pub fn requested(url: &url::Url) -> bool {
+    url.query_pairs()
+        .any(|(name, value)| name == "feed" && value == "atom")
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let url = request.url()?;
+    let origin = url.origin().ascii_serialization();
+    let filter = crate::filter::EntryFilter::from_config(config, false);
+    let files = crate::walk::recent(mount, path, LIMIT, &filter).await?;
+    let updated = files
+        .first()
+        .map(|(_, object)| object.uploaded)
+        .unwrap_or_else(chrono::Utc::now);
+    let directory = format!("{origin}{}", crate::href(path));
+
+    let mut feed = format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n",
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\">\n",
+            "<title>/{title}</title>\n",
+            "<id>{directory}</id>\n",
+            "<link href=\"{directory}\"/>\n",
+            "<link rel=\"self\" href=\"{url}\"/>\n",
+            "<updated>{updated}</updated>\n",
+            "<author><name>{host}</name></author>\n",
+        ),
+        title = crate::escape_xml(path),
+        directory = crate::escape_xml(&directory),
+        url = crate::escape_xml(url.as_str()),
+        updated = updated.to_rfc3339(),
+        host = crate::escape_xml(url.host_str().unwrap_or_default()),
+    );
+    for (path, object) in &files {
+        let link = crate::escape_xml(&format!("{origin}{}", crate::href(path)));
+        feed.push_str(&format!(
+            concat!(
+                "<entry>\n",
+                "<title>{title}</title>\n",
+                "<id>{link}</id>\n",
+                "<link href=\"{link}\"/>\n",
+                "<updated>{updated}</updated>\n",
+                "<summary>{size}</summary>\n",
+                "</entry>\n",
+            ),
+            title = crate::escape_xml(path),
+            link = link,
+            updated = object.uploaded.to_rfc3339(),
+            size = humansize::format_size(object.size, crate::sizes::options(config, Some(&url))),
+        ));
+    }
+    feed.push_str("</feed>\n");
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "application/atom+xml; charset=utf-8")?;
+    Ok(worker::Response::ok(feed)?.with_headers(headers))
+}
@@ -0,0 +1,387 @@
+pub async fn handle(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    context: &worker::Context,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let store = mount.store.as_ref();
+    let key = mount.key(path);
+
+    let clean_urls = config.clean_urls;
+    if let Some(stem) = path
+        .strip_suffix(".html")
+        .filter(|stem| clean_urls && !stem.is_empty() && !stem.ends_with('/'))
+    {
+        return crate::redirect_to_path(request, stem, 301);
+    }
+
+    if let Some(response) = crate::qr::handle(request, environment, config, store, &key).await? {
+        return Ok(response);
+    }
+    if let Some(response) = crate::meta::handle(request, store, path, &key).await? {
+        return Ok(response);
+    }
+    if let Some(response) =
+        crate::torrent::handle(request, environment, config, mount, path, &key).await?
+    {
+        return Ok(response);
+    }
+    if let Some(response) = crate::preview::handle(request, config, store, path, &key).await? {
+        return Ok(response);
+    }
+    if let Some(response) = serve_file(request, environment, config, context, store, &key).await? {
+        if is_download(request, &response)? {
+            crate::counters::increment(environment, context, mount, &key);
+            crate::webhooks::notify(config, context, request, crate::webhooks::Event::Download)?;
+        }
+        return Ok(response);
+    }
+    let directory = store
+        .list(crate::store::ListOptions {
+            prefix: format!("{key}/"),
+            limit: Some(1),
+            ..Default::default()
+        })
+        .await?;
+    if !directory.objects.is_empty() {
+        return crate::redirect_to_path(request, &format!("{path}/"), 301);
+    }
+    if clean_urls {
+        let page = format!("{key}.html");
+        if let Some(response) =
+            serve_file(request, environment, config, context, store, &page).await?
+        {
+            if is_download(request, &response)? {
+                crate::counters::increment(environment, context, mount, &page);
+                crate::webhooks::notify(
+                    config,
+                    context,
+                    request,
+                    crate::webhooks::Event::Download,
+                )?;
+            }
+            return Ok(response);
+        }
+    }
+    if config.case_insensitive_fallback {
+        if let Some(found) = crate::case_insensitive::find(config, mount, path).await? {
+            return crate::redirect_to_path(request, &found, 301);
+        }
+    }
+    if config.spa_fallback && crate::extension(&key).is_none() {
+        let index = format!("{}index.html", mount.key_prefix);
+        if let Some(response) =
+            serve_file(request, environment, config, context, store, &index).await?
+        {
+            return Ok(response);
+        }
+    }
+    crate::errors::response(environment, config, Some(mount), 404, "Not Found").await
+}
+
+pub async fn generated(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    context: &worker::Context,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<Option<worker::Response>> {
+    if ![crate::robots::PATH, crate::feed::PATH, crate::sitemap::PATH].contains(&path)
+        || mount.store.head(&mount.key(path)).await?.is_some()
+    {
+        return Ok(None);
+    }
+    match path {
+        crate::robots::PATH => crate::robots::response(config),
+        crate::feed::PATH => crate::feed::handle(request, config, mount, "")
+            .await
+            .map(Some),
+        _ => crate::sitemap::handle(request, environment, config, context, mount)
+            .await
+            .map(Some),
+    }
+}
+
+fn is_download(request: &worker::Request, response: &worker::Response) -> worker::Result<bool> {
+    if request.method() != worker::Method::Get {
+        return Ok(false);
+    }
+    Ok(match response.status_code() {
+        200 => true,
+        206 => response
+            .headers()
+            .get("content-range")?
+            .is_some_and(|range| range.starts_with("bytes 0-")),
+        _ => false,
+    })
+}
+
+fn file_headers(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    key: &str,
+    headers: &mut worker::Headers,
+) -> worker::Result<()> {
+    crate::mime::apply(config, key, headers)?;
+    if crate::preview::for_crawlers(&headers.get("content-type")?.unwrap_or_default()) {
+        headers.append("vary", "user-agent")?;
+    }
+    crate::cache_control::apply(config, key, headers)?;
+    crate::robots::tag(&config.robots_tag_files, headers)?;
+    crate::content_disposition::apply(&request.url()?, key, headers)
+}
+
+async fn serve_file(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    context: &worker::Context,
+    store: &dyn crate::store::ObjectStore,
+    key: &str,
+) -> worker::Result<Option<worker::Response>> {
+    let precompressed = config.precompressed;
+    let metered = request.method() == worker::Method::Get;
+    let range = match metered {
+        true => request.headers().get("range")?,
+        false => None,
+    };
+    if precompressed && range.is_none() {
+        if let Some(mut response) = crate::precompressed::get(request, store, key).await? {
+            let bytes = response
+                .headers()
+                .get("content-length")?
+                .and_then(|length| length.parse().ok())
+                .unwrap_or_default();
+            if metered {
+                if let Some(response) =
+                    crate::quota::consume(request, environment, config, context, key, bytes).await?
+                {
+                    return Ok(Some(response));
+                }
+            }
+            file_headers(request, config, key, response.headers_mut())?;
+            return crate::throttle::apply(request, config, response).map(Some);
+        }
+    }
+
+    let if_range = request.headers().get("if-range")?;
+    let plan = plan(
+        store,
+        key,
+        &request.method(),
+        range.as_deref(),
+        if_range.as_deref(),
+    )
+    .await?;
+    match plan {
+        None => return Ok(None),
+        Some(Plan::Get) => {}
+        Some(Plan::Head(object)) => {
+            let headers = object_headers(request, config, key, &object)?;
+            return Ok(Some(worker::Response::empty()?.with_headers(headers)));
+        }
+        Some(Plan::Unsatisfiable(size)) => return crate::ranges::unsatisfiable(size).map(Some),
+        Some(Plan::Partial(object, ranges)) => {
+            let bytes = ranges.iter().map(crate::ranges::Range::length).sum();
+            if let Some(response) =
+                crate::quota::consume(request, environment, config, context, key, bytes).await?
+            {
+                return Ok(Some(response));
+            }
+            let headers = object_headers(request, config, key, &object)?;
+            return match crate::ranges::serve(store, &object, &ranges, headers).await? {
+                Some(response) => crate::throttle::apply(request, config, response).map(Some),
+                None => Ok(None),
+            };
+        }
+    }
+
+    let Some((object, body)) = store.get(key).await? else {
+        return Ok(None);
+    };
+    if metered {
+        if let Some(response) =
+            crate::quota::consume(request, environment, config, context, key, object.size).await?
+        {
+            return Ok(Some(response));
+        }
+    }
+    let headers = object_headers(request, config, key, &object)?;
+    let response = body.into_response()?.with_headers(headers);
+    crate::throttle::apply(request, config, response).map(Some)
+}
+
+enum Plan {
+    Get,
+    Head(crate::store::Object),
+    Unsatisfiable(u64),
+    Partial(crate::store::Object, Vec<crate::ranges::Range>),
+}
+
+async fn plan(
+    store: &dyn crate::store::ObjectStore,
+    key: &str,
+    method: &worker::Method,
+    range: Option<&str>,
+    if_range: Option<&str>,
+) -> worker::Result<Option<Plan>> {
+    if *method == worker::Method::Head {
+        return Ok(store.head(key).await?.map(Plan::Head));
+    }
+    let Some(range) = range else {
+        return Ok(Some(Plan::Get));
+    };
+    let Some(object) = store.head(key).await? else {
+        return Ok(None);
+    };
+    if if_range.is_some_and(|if_range| !crate::ranges::matches(if_range, &object)) {
+        return Ok(Some(Plan::Get));
+    }
+    Ok(Some(match crate::ranges::parse(range, object.size) {
+        crate::ranges::Ranges::Full => Plan::Get,
+        crate::ranges::Ranges::Unsatisfiable => Plan::Unsatisfiable(object.size),
+        crate::ranges::Ranges::Partial(ranges) => Plan::Partial(object, ranges),
+    }))
+}
+
+fn object_headers(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    key: &str,
+    object: &crate::store::Object,
+) -> worker::Result<worker::Headers> {
+    let mut headers = worker::Headers::new();
+    object.write_http_metadata(&mut headers)?;
+    object.write_integrity_headers(&mut headers)?;
+    file_headers(request, config, key, &mut headers)?;
+    headers.set("content-length", &object.size.to_string())?;
+    headers.set("accept-ranges", "bytes")?;
+    headers.set(
+        "last-modified",
+        &object
+            .uploaded
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string(),
+    )?;
+    if config.precompressed {
+        headers.append("vary", "accept-encoding")?;
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+    use crate::store::ObjectStore;
+
+    fn planned(
+        store: &crate::store::MemoryStore,
+        key: &str,
+        method: worker::Method,
+        range: Option<&str>,
+        if_range: Option<&str>,
+    ) -> Option<Plan> {
+        plan(store, key, &method, range, if_range)
+            .now_or_never()
+            .unwrap()
+            .unwrap()
+    }
+
+    fn store() -> crate::store::MemoryStore {
+        crate::store::MemoryStore::with_files(&[("file.txt", "0123456789")])
+    }
+
+    #[test]
+    fn gets_whole_files() {
+        let store = store();
+        let plan = planned(&store, "file.txt", worker::Method::Get, None, None);
+        assert!(matches!(plan, Some(Plan::Get)));
+        let plan = planned(
+            &store,
+            "file.txt",
+            worker::Method::Get,
+            Some("lines=1"),
+            None,
+        );
+        assert!(matches!(plan, Some(Plan::Get)));
+    }
+
+    #[test]
+    fn heads_without_reading_bodies() {
+        let store = store();
+        let plan = planned(
+            &store,
+            "file.txt",
+            worker::Method::Head,
+            Some("bytes=0-1"),
+            None,
+        );
+        assert!(matches!(plan, Some(Plan::Head(object)) if object.size == 10));
+        assert!(planned(&store, "missing", worker::Method::Head, None, None).is_none());
+    }
+
+    #[test]
+    fn serves_ranges() {
+        let store = store();
+        let plan = planned(
+            &store,
+            "file.txt",
+            worker::Method::Get,
+            Some("bytes=2-4,-2"),
+            None,
+        );
+        let Some(Plan::Partial(object, ranges)) = plan else {
+            panic!("the ranges must be served");
+        };
+        assert_eq!(object.key, "file.txt");
+        let spans: Vec<_> = ranges
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect();
+        assert_eq!(spans, [(2, 4), (8, 9)]);
+        let plan = planned(
+            &store,
+            "file.txt",
+            worker::Method::Get,
+            Some("bytes=10-"),
+            None,
+        );
+        assert!(matches!(plan, Some(Plan::Unsatisfiable(10))));
+        assert!(planned(
+            &store,
+            "missing",
+            worker::Method::Get,
+            Some("bytes=0-1"),
+            None
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn falls_back_to_the_whole_file_for_stale_ranges() {
+        let store = store();
+        let etag = store
+            .head("file.txt")
+            .now_or_never()
+            .unwrap()
+            .unwrap()
+            .unwrap()
+            .etag;
+        let range = Some("bytes=0-1");
+        let plan = planned(&store, "file.txt", worker::Method::Get, range, Some(&etag));
+        assert!(matches!(plan, Some(Plan::Partial(..))));
+        let plan = planned(
+            &store,
+            "file.txt",
+            worker::Method::Get,
+            range,
+            Some("\"stale\""),
+        );
+        assert!(matches!(plan, Some(Plan::Get)));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::EntryType;
+
+#[derive(Clone)]
+pub struct EntryFilter {
+    exclude: Vec<String>,
+    hide_precompressed: bool,
+    query: Option<String>,
+}
+
+impl EntryFilter {
+    pub fn from_config(config: &crate::config::Config, show_all: bool) -> Self {
+        let mut filter = Self {
+            exclude: if show_all {
+                Vec::new()
+            } else {
+                config.listing_exclude.clone()
+            },
+            hide_precompressed: !show_all && config.precompressed,
+            query: None,
+        };
+        if let Some(prefix) = &config.trash_prefix {
+            if !filter.excludes(prefix) {
+                filter.exclude.push(prefix.trim_end_matches('/').to_owned());
+            }
+        }
+        filter
+    }
+
+    pub fn matching(mut self, query: Option<&str>) -> Self {
+        self.query = query
+            .map(str::trim)
+            .filter(|query| !query.is_empty())
+            .map(str::to_lowercase);
+        self
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    pub fn apply(&self, key_prefix: &str, entries: &mut Vec<(EntryType, String)>) {
+        if !self.exclude.is_empty() {
+            entries.retain(|(_, key)| {
+                !self.excludes(key.strip_prefix(key_prefix).unwrap_or(key)) && !self.below(key)
+            });
+        }
+        if self.hide_precompressed {
+            crate::precompressed::hide_variants(entries);
+        }
+        if let Some(query) = &self.query {
+            entries.retain(|(_, key)| {
+                key.strip_prefix(key_prefix)
+                    .unwrap_or(key)
+                    .trim_end_matches('/')
+                    .to_lowercase()
+                    .contains(query.as_str())
+            });
+        }
+    }
+
+    pub fn excludes(&self, path: &str) -> bool {
+        path.trim_end_matches('/').split('/').any(|name| {
+            self.exclude
+                .iter()
+                .filter(|pattern| !pattern.contains('/'))
+                .any(|pattern| crate::pattern::matches(pattern, name).is_some())
+        }) || self.below(path)
+    }
+
+    /// Patterns with a slash exclude that path and everything below it.
+    fn below(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        self.exclude
+            .iter()
+            .filter(|pattern| pattern.contains('/'))
+            .any(|pattern| {
+                path.strip_prefix(pattern.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excludes_the_trash() {
+        let config = crate::config::Config::from_vars(&[("TRASH_PREFIX", "trash")]);
+        let filter = EntryFilter::from_config(&config, true);
+        assert!(filter.excludes("trash/"));
+        assert!(filter.excludes("trash/2024-01-01T00:00:00.000Z/a.txt"));
+        assert!(filter.excludes("docs/trash/"));
+        assert!(!filter.excludes("trashed/"));
+
+        let config = crate::config::Config::from_vars(&[("TRASH_PREFIX", "admin/trash")]);
+        let filter = EntryFilter::from_config(&config, false);
+        assert!(filter.excludes("admin/trash/"));
+        assert!(filter.excludes("admin/trash/a.txt"));
+        assert!(!filter.excludes("admin/"));
+        assert!(!filter.excludes("docs/admin/trash/"));
+        let mut entries = vec![
+            (EntryType::Directory, "admin/trash/".to_owned()),
+            (EntryType::Directory, "admin/docs/".to_owned()),
+        ];
+        filter.apply("admin/", &mut entries);
+        assert_eq!(entries.len(), 1);
+
+        let config = crate::config::Config::from_vars(&[("TRASH_PREFIX", ".trash")]);
+        let filter = EntryFilter::from_config(&config, false);
+        assert_eq!(filter.exclude, config.listing_exclude);
+    }
+}
@@ -0,0 +1,9 @@
+pub fn face(url: Option<&str>) -> impl markup::Render {
+    markup::raw(match url {
+        Some(url) => format!(
+            "@font-face {{ font-family: 'Inconsolata'; src: url('{}'); font-display: swap; }}",
+            url.replace(['\'', '\\', '<'], "")
+        ),
+        None => String::new(),
+    })
+}
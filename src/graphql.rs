@@ -0,0 +1,669 @@
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use serde_json::Value;
+
+use crate::EntryType;
+
+pub const PATH: &str = "/graphql";
+
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_DEPTH: usize = 8;
+const MAX_FIELDS: usize = 200;
+
+#[derive(serde::Deserialize)]
+struct Body {
+    query: String,
+    #[serde(default)]
+    variables: Option<serde_json::Map<String, Value>>,
+}
+
+struct Field {
+    alias: Option<String>,
+    name: String,
+    arguments: Vec<(String, Input)>,
+    selections: Vec<Field>,
+}
+
+impl Field {
+    fn key(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.name.clone())
+    }
+}
+
+enum Input {
+    Null,
+    Boolean(bool),
+    Int(i64),
+    String(String),
+    Variable(String),
+}
+
+pub async fn handle(
+    mut request: worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<worker::Response> {
+    let url = request.url()?;
+    let body = if request.method() == worker::Method::Post {
+        match request.json::<Body>().await {
+            Ok(body) => body,
+            Err(err) => return failure(400, &err.to_string()),
+        }
+    } else {
+        let parameter = |name: &str| {
+            url.query_pairs()
+                .find(|(parameter, _)| parameter == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        let Some(query) = parameter("query") else {
+            return failure(400, "missing `query`");
+        };
+        let variables = match parameter("variables").map(|value| serde_json::from_str(&value)) {
+            Some(Ok(variables)) => variables,
+            Some(Err(err)) => return failure(400, &err.to_string()),
+            None => None,
+        };
+        Body { query, variables }
+    };
+    let selections = match Parser::new(&body.query).document() {
+        Ok(selections) => selections,
+        Err(message) => return failure(400, &message),
+    };
+
+    let context = Context {
+        environment,
+        config,
+        host: url.host_str().unwrap_or_default().to_owned(),
+        variables: body.variables.unwrap_or_default(),
+        filter: crate::filter::EntryFilter::from_config(config, false),
+        sorting: crate::sorting::Sorting::from_request(config, None),
+        disk_usage: crate::du::DiskUsage::from_config(environment, config),
+    };
+    match context.query(&selections).await {
+        Ok(data) => worker::Response::from_json(&serde_json::json!({ "data": data })),
+        Err(err) => worker::Response::from_json(&serde_json::json!({
+            "data": null,
+            "errors": [{ "message": err.to_string() }],
+        })),
+    }
+}
+
+fn failure(status: u16, message: &str) -> worker::Result<worker::Response> {
+    Ok(
+        worker::Response::from_json(&serde_json::json!({ "errors": [{ "message": message }] }))?
+            .with_status(status),
+    )
+}
+
+fn error(message: String) -> worker::Error {
+    worker::Error::RustError(message)
+}
+
+struct Context<'a> {
+    environment: &'a worker::Env,
+    config: &'a crate::config::Config,
+    host: String,
+    variables: serde_json::Map<String, Value>,
+    filter: crate::filter::EntryFilter,
+    sorting: crate::sorting::Sorting,
+    disk_usage: crate::du::DiskUsage,
+}
+
+impl Context<'_> {
+    async fn query(&self, selections: &[Field]) -> worker::Result<Value> {
+        let mut data = serde_json::Map::new();
+        for field in selections {
+            let value = match field.name.as_str() {
+                "__typename" => "Query".into(),
+                "directory" => {
+                    let path = self.string(field, "path")?.unwrap_or_default();
+                    let path = path.trim_matches('/');
+                    let path = match path.is_empty() {
+                        true => String::new(),
+                        false => format!("{path}/"),
+                    };
+                    self.directory(path, &field.selections).await?
+                }
+                "file" => {
+                    let Some(path) = self.string(field, "path")? else {
+                        return Err(error("`file` requires a `path`".into()));
+                    };
+                    self.file(path.trim_start_matches('/'), &field.selections)
+                        .await?
+                }
+                "search" => {
+                    let Some(query) = self.string(field, "query")? else {
+                        return Err(error("`search` requires a `query`".into()));
+                    };
+                    let path = self.string(field, "path")?.unwrap_or_default();
+                    let limit = match self.argument(field, "limit") {
+                        Some(limit) => limit
+                            .as_u64()
+                            .ok_or_else(|| error("`limit` must be a positive integer".into()))?
+                            as usize,
+                        None => DEFAULT_SEARCH_LIMIT,
+                    };
+                    self.search(
+                        &query,
+                        path.trim_start_matches('/'),
+                        limit,
+                        &field.selections,
+                    )
+                    .await?
+                }
+                name => return Err(error(format!("unknown field `{name}` on `Query`"))),
+            };
+            data.insert(field.key(), value);
+        }
+        Ok(data.into())
+    }
+
+    fn directory<'b>(
+        &'b self,
+        path: String,
+        selections: &'b [Field],
+    ) -> LocalBoxFuture<'b, worker::Result<Value>> {
+        async move {
+            let Some(mount) =
+                crate::mounts::resolve(self.environment, self.config, &self.host, &path)?
+            else {
+                return Ok(Value::Null);
+            };
+            let key_prefix = mount.key(&path);
+            if mount
+                .store
+                .head(&format!("{key_prefix}.noindex"))
+                .await?
+                .is_some()
+            {
+                return Ok(Value::Null);
+            }
+            let listed = selections
+                .iter()
+                .any(|field| matches!(field.name.as_str(), "files" | "directories"));
+            let (entries, objects) = match listed {
+                true => self.entries(&mount, &key_prefix).await?,
+                false => Default::default(),
+            };
+            let mut usage = None;
+
+            let mut directory = serde_json::Map::new();
+            for field in selections {
+                let value = match field.name.as_str() {
+                    "__typename" => "Directory".into(),
+                    "path" => if path.is_empty() { "/" } else { &path }.into(),
+                    "name" => path
+                        .trim_end_matches('/')
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default()
+                        .into(),
+                    "size" | "count" => {
+                        let usage: crate::du::Usage = match usage {
+                            Some(usage) => usage,
+                            None => *usage.insert(self.disk_usage.usage(&mount, &path).await?),
+                        };
+                        match field.name.as_str() {
+                            "size" => usage.size.into(),
+                            _ => usage.count.into(),
+                        }
+                    }
+                    "files" => entries
+                        .iter()
+                        .filter(|(entry_type, _)| *entry_type != EntryType::Directory)
+                        .filter_map(|(_, key)| {
+                            let name = key.strip_prefix(key_prefix.as_str())?;
+                            Some((format!("{path}{name}"), objects.get(name)?))
+                        })
+                        .map(|(path, object)| self.file_value(&path, object, &field.selections))
+                        .collect::<worker::Result<Vec<_>>>()?
+                        .into(),
+                    "directories" => {
+                        let mut directories = Vec::new();
+                        for (_, key) in entries
+                            .iter()
+                            .filter(|(entry_type, _)| *entry_type == EntryType::Directory)
+                        {
+                            let name = key.strip_prefix(key_prefix.as_str()).unwrap_or(key);
+                            directories.push(
+                                self.directory(format!("{path}{name}"), &field.selections)
+                                    .await?,
+                            );
+                        }
+                        directories.into()
+                    }
+                    name => return Err(error(format!("unknown field `{name}` on `Directory`"))),
+                };
+                directory.insert(field.key(), value);
+            }
+            Ok(directory.into())
+        }
+        .boxed_local()
+    }
+
+    async fn entries(
+        &self,
+        mount: &crate::mounts::Mount,
+        key_prefix: &str,
+    ) -> worker::Result<(Vec<(EntryType, String)>, crate::listing::Objects)> {
+        let mut entries = Vec::new();
+        let mut objects = crate::listing::Objects::new();
+        let mut cursor = None;
+        loop {
+            let listing =
+                crate::listing::list(mount.store.as_ref(), key_prefix, cursor, true).await?;
+            entries.extend(crate::listing::list_entries(
+                &listing,
+                key_prefix,
+                &self.filter,
+            ));
+            objects.extend(crate::listing::objects_by_name(
+                &listing.objects,
+                key_prefix,
+            ));
+            match listing.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        self.sorting.sort(&mut entries);
+        Ok((entries, objects))
+    }
+
+    async fn file(&self, path: &str, selections: &[Field]) -> worker::Result<Value> {
+        if path.is_empty() || path.ends_with('/') || self.filter.excludes(path) {
+            return Ok(Value::Null);
+        }
+        let Some(mount) = crate::mounts::resolve(self.environment, self.config, &self.host, path)?
+        else {
+            return Ok(Value::Null);
+        };
+        match mount.store.head(&mount.key(path)).await? {
+            Some(object) => self.file_value(path, &object, selections),
+            None => Ok(Value::Null),
+        }
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        path: &str,
+        limit: usize,
+        selections: &[Field],
+    ) -> worker::Result<Value> {
+        let Some(mount) = crate::mounts::resolve(self.environment, self.config, &self.host, path)?
+        else {
+            return Ok(Vec::<Value>::new().into());
+        };
+        let query = query.to_lowercase();
+        crate::walk::files(&mount, path, &self.filter)
+            .await?
+            .iter()
+            .filter(|(path, _)| {
+                let name = path.rsplit('/').next().unwrap_or(path);
+                name.to_lowercase().contains(&query)
+            })
+            .take(limit)
+            .map(|(path, object)| self.file_value(path, object, selections))
+            .collect::<worker::Result<Vec<_>>>()
+            .map(Value::from)
+    }
+
+    fn file_value(
+        &self,
+        path: &str,
+        object: &crate::store::Object,
+        selections: &[Field],
+    ) -> worker::Result<Value> {
+        let mut file = serde_json::Map::new();
+        for field in selections {
+            let value = match field.name.as_str() {
+                "__typename" => "File".into(),
+                "path" => path.into(),
+                "name" => path.rsplit('/').next().unwrap_or(path).into(),
+                "url" => crate::href(path).into(),
+                "size" => object.size.into(),
+                "uploaded" => object.uploaded.to_rfc3339().into(),
+                "etag" => object.etag.clone().into(),
+                "contentType" => {
+                    let mut headers = worker::Headers::new();
+                    object.write_http_metadata(&mut headers)?;
+                    crate::mime::apply(self.config, &object.key, &mut headers)?;
+                    headers.get("content-type")?.into()
+                }
+                "checksums" => pairs(field, "Checksum", ["algorithm", "value"], &object.checksums)?,
+                "metadata" => pairs(field, "Metadata", ["key", "value"], &object.custom_metadata)?,
+                name => return Err(error(format!("unknown field `{name}` on `File`"))),
+            };
+            file.insert(field.key(), value);
+        }
+        Ok(file.into())
+    }
+
+    fn argument(&self, field: &Field, name: &str) -> Option<Value> {
+        let (_, input) = field
+            .arguments
+            .iter()
+            .find(|(argument, _)| argument == name)?;
+        let value = match input {
+            Input::Null => Value::Null,
+            Input::Boolean(value) => (*value).into(),
+            Input::Int(value) => (*value).into(),
+            Input::String(value) => value.clone().into(),
+            Input::Variable(variable) => self.variables.get(variable).cloned()?,
+        };
+        (!value.is_null()).then_some(value)
+    }
+
+    fn string(&self, field: &Field, name: &str) -> worker::Result<Option<String>> {
+        match self.argument(field, name) {
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(_) => Err(error(format!("`{name}` must be a string"))),
+            None => Ok(None),
+        }
+    }
+}
+
+fn pairs(
+    field: &Field,
+    typename: &str,
+    names: [&str; 2],
+    pairs: &[(String, String)],
+) -> worker::Result<Value> {
+    pairs
+        .iter()
+        .map(|(first, second)| {
+            let mut pair = serde_json::Map::new();
+            for selection in &field.selections {
+                let value = match selection.name.as_str() {
+                    "__typename" => typename.into(),
+                    name if name == names[0] => first.clone().into(),
+                    name if name == names[1] => second.clone().into(),
+                    name => return Err(error(format!("unknown field `{name}` on `{typename}`"))),
+                };
+                pair.insert(selection.key(), value);
+            }
+            Ok(pair.into())
+        })
+        .collect::<worker::Result<Vec<Value>>>()
+        .map(Value::from)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+    depth: usize,
+    fields: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            position: 0,
+            depth: 0,
+            fields: 0,
+        }
+    }
+
+    fn document(mut self) -> Result<Vec<Field>, String> {
+        self.skip();
+        if self.peek() != Some('{') {
+            let operation = self.name()?;
+            if operation != "query" {
+                return Err(format!("unsupported operation `{operation}`"));
+            }
+            self.skip();
+            if self
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            {
+                self.name()?;
+                self.skip();
+            }
+            if self.eat('(') {
+                while !self.eat(')') {
+                    match self.peek() {
+                        Some('"') => {
+                            self.string()?;
+                        }
+                        Some(_) => self.position += self.peek().map_or(1, char::len_utf8),
+                        None => return Err("unterminated variable definitions".into()),
+                    }
+                }
+            }
+        }
+        let selections = self.selections()?;
+        self.skip();
+        if self.position < self.source.len() {
+            return Err(format!(
+                "unexpected input at offset {}; only a single query is supported",
+                self.position
+            ));
+        }
+        Ok(selections)
+    }
+
+    fn selections(&mut self) -> Result<Vec<Field>, String> {
+        self.skip();
+        self.expect('{')?;
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!(
+                "queries must not be nested deeper than {MAX_DEPTH} levels"
+            ));
+        }
+        let mut fields = Vec::new();
+        loop {
+            self.skip();
+            if self.eat('}') {
+                break;
+            }
+            if self.source[self.position..].starts_with("...") {
+                return Err("fragments are not supported".into());
+            }
+            self.fields += 1;
+            if self.fields > MAX_FIELDS {
+                return Err(format!(
+                    "queries must not select more than {MAX_FIELDS} fields"
+                ));
+            }
+            fields.push(self.field()?);
+        }
+        if fields.is_empty() {
+            return Err("selection sets must not be empty".into());
+        }
+        self.depth -= 1;
+        Ok(fields)
+    }
+
+    fn field(&mut self) -> Result<Field, String> {
+        let mut alias = None;
+        let mut name = self.name()?;
+        self.skip();
+        if self.eat(':') {
+            self.skip();
+            alias = Some(name);
+            name = self.name()?;
+            self.skip();
+        }
+        let mut arguments = Vec::new();
+        if self.eat('(') {
+            loop {
+                self.skip();
+                if self.eat(')') {
+                    break;
+                }
+                let argument = self.name()?;
+                self.skip();
+                self.expect(':')?;
+                self.skip();
+                arguments.push((argument, self.value()?));
+            }
+            self.skip();
+        }
+        let selections = match self.peek() {
+            Some('{') => self.selections()?,
+            _ => Vec::new(),
+        };
+        Ok(Field {
+            alias,
+            name,
+            arguments,
+            selections,
+        })
+    }
+
+    fn value(&mut self) -> Result<Input, String> {
+        match self.peek() {
+            Some('$') => {
+                self.position += 1;
+                Ok(Input::Variable(self.name()?))
+            }
+            Some('"') => self.string().map(Input::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let rest = &self.source[self.position..];
+                let end = rest
+                    .char_indices()
+                    .find(|(index, c)| !(c.is_ascii_digit() || (*index == 0 && *c == '-')))
+                    .map_or(rest.len(), |(index, _)| index);
+                self.position += end;
+                rest[..end]
+                    .parse()
+                    .map(Input::Int)
+                    .map_err(|_| format!("invalid integer `{}`", &rest[..end]))
+            }
+            _ => match self.name()?.as_str() {
+                "true" => Ok(Input::Boolean(true)),
+                "false" => Ok(Input::Boolean(false)),
+                "null" => Ok(Input::Null),
+                value => Err(format!("unsupported value `{value}`")),
+            },
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next().ok_or("unterminated string")? {
+                '"' => return Ok(value),
+                '\\' => value.push(match self.next().ok_or("unterminated string")? {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let digits = self
+                            .source
+                            .get(self.position..self.position + 4)
+                            .ok_or("invalid unicode escape")?;
+                        self.position += 4;
+                        u32::from_str_radix(digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or("invalid unicode escape")?
+                    }
+                    c => c,
+                }),
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let rest = &self.source[self.position..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if end == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(format!("expected a name at offset {}", self.position));
+        }
+        self.position += end;
+        Ok(rest[..end].to_owned())
+    }
+
+    fn skip(&mut self) {
+        loop {
+            let rest = &self.source[self.position..];
+            let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+            self.position += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                break;
+            }
+            self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.position += c.len_utf8();
+        }
+        eaten
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(format!("expected `{c}` at offset {}", self.position)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_queries() {
+        let selections = Parser::new(
+            "query Files($path: String) { docs: directory(path: $path) { files { name } } }",
+        )
+        .document()
+        .unwrap();
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].key(), "docs");
+        assert_eq!(selections[0].name, "directory");
+        assert!(matches!(
+            selections[0].arguments[..],
+            [(ref name, Input::Variable(ref variable))] if name == "path" && variable == "path"
+        ));
+        assert_eq!(selections[0].selections[0].selections[0].name, "name");
+    }
+
+    #[test]
+    fn rejects_deep_queries() {
+        let query = |depth: usize| {
+            format!(
+                "{}name{}",
+                "{ directory ".repeat(depth - 1) + "{ ",
+                " }".repeat(depth)
+            )
+        };
+        assert!(Parser::new(&query(MAX_DEPTH)).document().is_ok());
+        let err = Parser::new(&query(MAX_DEPTH + 1)).document().err().unwrap();
+        assert!(err.contains("nested deeper"));
+    }
+
+    #[test]
+    fn rejects_wide_queries() {
+        let query = |fields: usize| format!("{{ {} }}", "name ".repeat(fields));
+        assert!(Parser::new(&query(MAX_FIELDS)).document().is_ok());
+        let err = Parser::new(&query(MAX_FIELDS + 1))
+            .document()
+            .err()
+            .unwrap();
+        assert!(err.contains("more than"));
+    }
+}
@@ -0,0 +1,70 @@
+struct Rule {
+    pattern: String,
+    set: Vec<(String, String)>,
+    remove: Vec<String>,
+}
+
+pub async fn apply(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    response: worker::Response,
+) -> worker::Result<worker::Response> {
+    let Some(key) = &config.headers_file else {
+        return Ok(response);
+    };
+    let rules = crate::bucket_file::load(request, environment, config, key, parse).await?;
+
+    let url = request.url()?;
+    let absolute = format!(
+        "{}://{}{}",
+        url.scheme(),
+        url.host_str().unwrap_or_default(),
+        url.path()
+    );
+    let mut headers = response.headers().clone();
+    for rule in rules.iter() {
+        let input = if rule.pattern.starts_with('/') {
+            url.path()
+        } else {
+            absolute.as_str()
+        };
+        let Some(captures) = crate::pattern::matches(&rule.pattern, input) else {
+            continue;
+        };
+        for name in &rule.remove {
+            headers.delete(name)?;
+        }
+        for (name, value) in &rule.set {
+            headers.append(name, &crate::pattern::substitute(value, captures.clone()))?;
+        }
+    }
+    Ok(response.with_headers(headers))
+}
+
+fn parse(contents: &str) -> Vec<Rule> {
+    let mut rules: Vec<Rule> = Vec::new();
+    for line in contents.lines() {
+        let indented = line.starts_with(char::is_whitespace);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match rules.last_mut() {
+            Some(rule) if indented => {
+                if let Some(name) = line.strip_prefix('!') {
+                    rule.remove.push(name.trim().to_owned());
+                } else if let Some((name, value)) = line.split_once(':') {
+                    rule.set
+                        .push((name.trim().to_owned(), value.trim().to_owned()));
+                }
+            }
+            _ => rules.push(Rule {
+                pattern: line.to_owned(),
+                set: Vec::new(),
+                remove: Vec::new(),
+            }),
+        }
+    }
+    rules
+}
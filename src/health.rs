@@ -0,0 +1,51 @@
+use futures_util::future::Either;
+
+pub const PATH: &str = "/_health";
+
+const TIMEOUT_MILLIS: u64 = 5000;
+
+pub async fn handle(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<worker::Response> {
+    let mut healthy = true;
+    let mut buckets = serde_json::Map::new();
+    for binding in crate::mounts::bindings(config) {
+        let status = match check(environment, binding).await {
+            Ok(()) => "ok".to_owned(),
+            Err(err) => {
+                healthy = false;
+                err.to_string()
+            }
+        };
+        buckets.insert(binding.to_owned(), status.into());
+    }
+    let mut headers = worker::Headers::new();
+    headers.set("cache-control", "no-store")?;
+    Ok(worker::Response::from_json(&serde_json::json!({
+        "status": if healthy { "ok" } else { "error" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": option_env!("GIT_COMMIT"),
+        "buckets": buckets,
+    }))?
+    .with_status(if healthy { 200 } else { 503 })
+    .with_headers(headers))
+}
+
+async fn check(environment: &worker::Env, binding: &str) -> worker::Result<()> {
+    let store = crate::store::binding(environment, binding)?;
+    let list = store.list(crate::store::ListOptions {
+        limit: Some(1),
+        ..Default::default()
+    });
+    let timeout = Box::pin(worker::Delay::from(std::time::Duration::from_millis(
+        TIMEOUT_MILLIS,
+    )));
+    let outcome = futures_util::future::select(list, timeout).await;
+    match outcome {
+        Either::Left((result, _)) => result.map(drop),
+        Either::Right(_) => Err(worker::Error::RustError(format!(
+            "no response within {TIMEOUT_MILLIS} ms"
+        ))),
+    }
+}
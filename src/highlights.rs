@@ -0,0 +1,58 @@
+pub struct Highlights {
+    pub recent: Vec<(String, chrono::DateTime<chrono::Utc>)>,
+    pub popular: Vec<(String, u64)>,
+}
+
+const KEY_PREFIX: &str = "recent:";
+
+pub async fn load(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    limit: usize,
+    mount: &crate::mounts::Mount,
+    filter: &crate::filter::EntryFilter,
+) -> worker::Result<Highlights> {
+    let mut popular: Vec<_> = crate::counters::all(environment, mount)
+        .await?
+        .into_iter()
+        .filter(|(path, _)| !filter.excludes(path))
+        .collect();
+    popular.sort_by(|(_, left), (_, right)| right.cmp(left));
+    popular.truncate(limit);
+    Ok(Highlights {
+        recent: recent(environment, config, limit, mount, filter).await?,
+        popular,
+    })
+}
+
+async fn recent(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    limit: usize,
+    mount: &crate::mounts::Mount,
+    filter: &crate::filter::EntryFilter,
+) -> worker::Result<Vec<(String, chrono::DateTime<chrono::Utc>)>> {
+    let cache = match filter.query() {
+        Some(_) => None,
+        None => environment.kv(crate::du::BINDING).ok(),
+    };
+    let name = format!("{KEY_PREFIX}{}:{}:{limit}", mount.binding, mount.key_prefix);
+    if let Some(cache) = &cache {
+        if let Some(recent) = cache.get(&name).json().await? {
+            return Ok(recent);
+        }
+    }
+    let recent: Vec<_> = crate::walk::recent(mount, &mount.path, limit, filter)
+        .await?
+        .into_iter()
+        .map(|(path, object)| (path, object.uploaded))
+        .collect();
+    if let Some(cache) = &cache {
+        cache
+            .put(&name, &recent)?
+            .expiration_ttl(config.du_cache_ttl.max(crate::du::MIN_CACHE_TTL))
+            .execute()
+            .await?;
+    }
+    Ok(recent)
+}
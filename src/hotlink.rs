@@ -0,0 +1,98 @@
+pub async fn check(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<Option<worker::Response>> {
+    let Some(allowed_hosts) = &config.hotlink_allowed_hosts else {
+        return Ok(None);
+    };
+    if is_redirect_target(config, &request.url()?) {
+        return Ok(None);
+    }
+    let Some(referer) = request.headers().get("referer")? else {
+        return Ok(None);
+    };
+    let Some(referer_host) = url::Url::parse(&referer)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+    else {
+        return Ok(None);
+    };
+
+    let own_host = request.url()?.host_str().map(str::to_ascii_lowercase);
+    if own_host.as_deref() == Some(referer_host.as_str())
+        || allowed_hosts
+            .iter()
+            .any(|pattern| host_matches(pattern, &referer_host))
+    {
+        return Ok(None);
+    }
+
+    match &config.hotlink_redirect_url {
+        Some(location) => crate::redirect(location, 302).map(Some),
+        None => crate::errors::for_request(request, environment, config, 403, "Forbidden")
+            .await
+            .map(Some),
+    }
+}
+
+pub fn is_redirect_target(config: &crate::config::Config, url: &url::Url) -> bool {
+    config
+        .hotlink_redirect_url
+        .as_deref()
+        .and_then(|location| url.join(location).ok())
+        .is_some_and(|target| target.host_str() == url.host_str() && target.path() == url.path())
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exempts_the_redirect_target() {
+        let url = |url: &str| url::Url::parse(url).unwrap();
+        let config = crate::config::Config::from_vars(&[("HOTLINK_REDIRECT_URL", "/hotlink.png")]);
+        assert!(is_redirect_target(
+            &config,
+            &url("https://example.com/hotlink.png?x=1")
+        ));
+        assert!(!is_redirect_target(
+            &config,
+            &url("https://example.com/file.png")
+        ));
+        let config = crate::config::Config::from_vars(&[(
+            "HOTLINK_REDIRECT_URL",
+            "https://example.com/hotlink.png",
+        )]);
+        assert!(is_redirect_target(
+            &config,
+            &url("https://example.com/hotlink.png")
+        ));
+        assert!(!is_redirect_target(
+            &config,
+            &url("https://other.example/hotlink.png")
+        ));
+        assert!(!is_redirect_target(
+            &crate::config::Config::default(),
+            &url("https://example.com/")
+        ));
+    }
+
+    #[test]
+    fn matches_wildcard_hosts() {
+        assert!(host_matches("*.example.com", "cdn.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+        assert!(host_matches("Example.com", "example.com"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const TRANSLATIONS: [(&str, &[(&str, &str)]); 4] = [
+    ("en", &[]),
+    (
+        "de",
+        &[
+            ("Name", "Name"),
+            ("Size", "Größe"),
+            ("Uploaded", "Hochgeladen"),
+            ("Downloads", "Downloads"),
+            ("Checksum", "Prüfsumme"),
+            ("▶ Play all", "▶ Alle abspielen"),
+            ("Recently added", "Zuletzt hinzugefügt"),
+            ("Most downloaded", "Am häufigsten heruntergeladen"),
+            (" in ", " in "),
+            ("1 download", "1 Download"),
+            ("{count} downloads", "{count} Downloads"),
+            ("1 file", "1 Datei"),
+            ("{count} files", "{count} Dateien"),
+            ("1 directory", "1 Verzeichnis"),
+            ("{count} directories", "{count} Verzeichnisse"),
+            (
+                "{size} in {files} below this directory",
+                "{size} in {files} unterhalb dieses Verzeichnisses",
+            ),
+            ("just now", "gerade eben"),
+            ("1 minute ago", "vor 1 Minute"),
+            ("{count} minutes ago", "vor {count} Minuten"),
+            ("1 hour ago", "vor 1 Stunde"),
+            ("{count} hours ago", "vor {count} Stunden"),
+            ("1 day ago", "vor 1 Tag"),
+            ("{count} days ago", "vor {count} Tagen"),
+            ("1 month ago", "vor 1 Monat"),
+            ("{count} months ago", "vor {count} Monaten"),
+            ("1 year ago", "vor 1 Jahr"),
+            ("{count} years ago", "vor {count} Jahren"),
+            ("%Y-%m-%d %H:%M:%S", "%d.%m.%Y %H:%M:%S"),
+            ("Filter", "Filtern"),
+            ("Load more", "Mehr laden"),
+            ("Back to /", "Zurück zu /"),
+            ("Bad Request", "Ungültige Anfrage"),
+            ("Forbidden", "Zugriff verweigert"),
+            ("Not Found", "Nicht gefunden"),
+            ("Method Not Allowed", "Methode nicht erlaubt"),
+            ("URI Too Long", "URI zu lang"),
+            ("Too Many Requests", "Zu viele Anfragen"),
+            ("Internal Server Error", "Interner Serverfehler"),
+        ],
+    ),
+    (
+        "es",
+        &[
+            ("Name", "Nombre"),
+            ("Size", "Tamaño"),
+            ("Uploaded", "Subido"),
+            ("Downloads", "Descargas"),
+            ("Checksum", "Suma de verificación"),
+            ("▶ Play all", "▶ Reproducir todo"),
+            ("Recently added", "Añadidos recientemente"),
+            ("Most downloaded", "Más descargados"),
+            (" in ", " en "),
+            ("1 download", "1 descarga"),
+            ("{count} downloads", "{count} descargas"),
+            ("1 file", "1 archivo"),
+            ("{count} files", "{count} archivos"),
+            ("1 directory", "1 directorio"),
+            ("{count} directories", "{count} directorios"),
+            (
+                "{size} in {files} below this directory",
+                "{size} en {files} dentro de este directorio",
+            ),
+            ("just now", "ahora mismo"),
+            ("1 minute ago", "hace 1 minuto"),
+            ("{count} minutes ago", "hace {count} minutos"),
+            ("1 hour ago", "hace 1 hora"),
+            ("{count} hours ago", "hace {count} horas"),
+            ("1 day ago", "hace 1 día"),
+            ("{count} days ago", "hace {count} días"),
+            ("1 month ago", "hace 1 mes"),
+            ("{count} months ago", "hace {count} meses"),
+            ("1 year ago", "hace 1 año"),
+            ("{count} years ago", "hace {count} años"),
+            ("%Y-%m-%d %H:%M:%S", "%d/%m/%Y %H:%M:%S"),
+            ("Filter", "Filtrar"),
+            ("Load more", "Cargar más"),
+            ("Back to /", "Volver a /"),
+            ("Bad Request", "Solicitud incorrecta"),
+            ("Forbidden", "Prohibido"),
+            ("Not Found", "No encontrado"),
+            ("Method Not Allowed", "Método no permitido"),
+            ("URI Too Long", "URI demasiado larga"),
+            ("Too Many Requests", "Demasiadas solicitudes"),
+            ("Internal Server Error", "Error interno del servidor"),
+        ],
+    ),
+    (
+        "fr",
+        &[
+            ("Name", "Nom"),
+            ("Size", "Taille"),
+            ("Uploaded", "Envoyé le"),
+            ("Downloads", "Téléchargements"),
+            ("Checksum", "Somme de contrôle"),
+            ("▶ Play all", "▶ Tout lire"),
+            ("Recently added", "Ajouts récents"),
+            ("Most downloaded", "Les plus téléchargés"),
+            (" in ", " dans "),
+            ("1 download", "1 téléchargement"),
+            ("{count} downloads", "{count} téléchargements"),
+            ("1 file", "1 fichier"),
+            ("{count} files", "{count} fichiers"),
+            ("1 directory", "1 dossier"),
+            ("{count} directories", "{count} dossiers"),
+            (
+                "{size} in {files} below this directory",
+                "{size} dans {files} sous ce dossier",
+            ),
+            ("just now", "à l’instant"),
+            ("1 minute ago", "il y a 1 minute"),
+            ("{count} minutes ago", "il y a {count} minutes"),
+            ("1 hour ago", "il y a 1 heure"),
+            ("{count} hours ago", "il y a {count} heures"),
+            ("1 day ago", "il y a 1 jour"),
+            ("{count} days ago", "il y a {count} jours"),
+            ("1 month ago", "il y a 1 mois"),
+            ("{count} months ago", "il y a {count} mois"),
+            ("1 year ago", "il y a 1 an"),
+            ("{count} years ago", "il y a {count} ans"),
+            ("%Y-%m-%d %H:%M:%S", "%d/%m/%Y %H:%M:%S"),
+            ("Filter", "Filtrer"),
+            ("Load more", "Charger plus"),
+            ("Back to /", "Retour à /"),
+            ("Bad Request", "Requête invalide"),
+            ("Forbidden", "Accès interdit"),
+            ("Not Found", "Introuvable"),
+            ("Method Not Allowed", "Méthode non autorisée"),
+            ("URI Too Long", "URI trop longue"),
+            ("Too Many Requests", "Trop de requêtes"),
+            ("Internal Server Error", "Erreur interne du serveur"),
+        ],
+    ),
+];
+
+#[derive(Clone)]
+pub struct Translator {
+    texts: Rc<HashMap<String, String>>,
+}
+
+impl Translator {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        let mut texts: HashMap<String, String> = TRANSLATIONS
+            .iter()
+            .find(|(language, _)| *language == config.language)
+            .into_iter()
+            .flat_map(|(_, texts)| texts.iter())
+            .map(|(text, translation)| (text.to_string(), translation.to_string()))
+            .collect();
+        if let Some(overrides) = config.translations.get(&config.language) {
+            texts.extend(overrides.clone());
+        }
+        Self {
+            texts: Rc::new(texts),
+        }
+    }
+
+    pub fn text<'a>(&'a self, text: &'a str) -> &'a str {
+        self.texts.get(text).map_or(text, String::as_str)
+    }
+
+    pub fn count(&self, one: &str, many: &str, count: u64) -> String {
+        match count {
+            1 => self.text(one).to_owned(),
+            _ => self.text(many).replace("{count}", &count.to_string()),
+        }
+    }
+}
+
+pub fn write_headers(
+    config: &crate::config::Config,
+    headers: &mut worker::Headers,
+) -> worker::Result<()> {
+    headers.set("content-language", &config.language)?;
+    if config.locale.is_none() {
+        headers.append("vary", "accept-language")?;
+    }
+    Ok(())
+}
+
+pub fn negotiate(
+    request: &worker::Request,
+    config: &crate::config::Config,
+) -> worker::Result<String> {
+    if let Some(locale) = &config.locale {
+        return Ok(locale.clone());
+    }
+    let accept_language = request
+        .headers()
+        .get("accept-language")?
+        .unwrap_or_default();
+    let mut ranges: Vec<(String, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parameters = range.split(';').map(str::trim);
+            let language = parameters.next()?.to_ascii_lowercase();
+            let quality = parameters
+                .find_map(|parameter| parameter.strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse().ok())?;
+            Some((language, quality))
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect();
+    ranges.sort_by(|(_, left), (_, right)| right.total_cmp(left));
+    Ok(ranges
+        .into_iter()
+        .find_map(|(range, _)| {
+            let language = range.split('-').next().unwrap_or_default();
+            available(config, language).then(|| language.to_owned())
+        })
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.into()))
+}
+
+fn available(config: &crate::config::Config, language: &str) -> bool {
+    TRANSLATIONS
+        .iter()
+        .any(|(available, _)| *available == language)
+        || config.translations.contains_key(language)
+}
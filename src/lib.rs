@@ -1,174 +1,478 @@
-const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+mod analytics;
+mod auth;
+mod bucket_file;
+mod cache_control;
+mod case_insensitive;
+mod checksums;
+pub mod config;
+mod content_disposition;
+mod cors;
+mod counters;
+mod dates;
+mod du;
+pub mod errors;
+pub mod etag;
+mod exif;
+mod feed;
+pub mod files;
+mod filter;
+mod fonts;
+mod graphql;
+mod headers_file;
+mod health;
+mod highlights;
+mod hotlink;
+mod i18n;
+pub mod listing;
+mod listing_cache;
+pub mod logging;
+mod meta;
+mod methods;
+mod mime;
+pub mod mounts;
+mod mtls;
+pub mod notifications;
+mod openapi;
+mod pattern;
+mod playlist;
+pub mod precompressed;
+mod preview;
+mod qr;
+mod quota;
+mod r2;
+mod ranges;
+mod rate_limit;
+mod redirects;
+mod reporting;
+mod robots;
+mod security;
+mod share;
+mod sitemap;
+mod sizes;
+mod sorting;
+pub mod store;
+mod throttle;
+mod thumbnails;
+mod torrent;
+mod trash;
+mod turnstile;
+mod walk;
+mod webhooks;
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use itertools::Itertools;
+
+type Guard = for<'a> fn(
+    &'a worker::Request,
+    &'a worker::Env,
+    &'a config::Config,
+) -> LocalBoxFuture<'a, worker::Result<Option<worker::Response>>>;
+
+const REQUEST_GUARDS: [Guard; 1] =
+    [|request, environment, config| mtls::check(request, environment, config).boxed_local()];
+
+const LISTING_GUARDS: [Guard; 1] = [|request, environment, config| {
+    rate_limit::check(request, environment, config, rate_limit::LISTINGS_BINDING).boxed_local()
+}];
+
+const FILE_GUARDS: [Guard; 3] = [
+    |request, environment, config| {
+        rate_limit::check(request, environment, config, rate_limit::DOWNLOADS_BINDING).boxed_local()
+    },
+    |request, environment, config| hotlink::check(request, environment, config).boxed_local(),
+    |request, _, config| std::future::ready(turnstile::check(request, config)).boxed_local(),
+];
 
 #[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub enum EntryType {
     Directory,
     File {
-        size: u32,
+        size: u64,
         uploaded: chrono::DateTime<chrono::Utc>,
     },
 }
 
-markup::define! {
-    EntryList<'a>(
-        key_prefix: &'a str,
-        readable_key_prefix: &'a str,
-        entries: Vec<(EntryType, String)>,
-        file_size_format_options: humansize::FormatSizeOptions
-    ) {
-        @markup::doctype()
-        html {
-            head {
-                meta[charset = "utf-8"] {}
-                title { @readable_key_prefix }
-                style {
-                    "@import url('https://fonts.googleapis.com/css2?family=Inconsolata:wght@300;400;600;700&family=Old+Standard+TT:ital,wght@0,400;0,700;1,400&display=swap');"
-                    "html { font-family: 'Inconsolata'; }"
-                    "body { padding: 1em; }"
-                    "* { margin: 0; padding: 0; }"
-                    "header { margin-bottom: 2em; }"
-                    "table { margin-left: 1em; }"
-                    "td, th { padding: 0.25em; max-width: 300px; }"
-                    "thead { background-color: #eee; }"
-                    "th { min-width: 100px; font-size: 1.1em; }"
-                }
-            }
-            body {
-                header {
-                    h1 {
-                        @readable_key_prefix
-                    }
-                }
-                table {
-                    thead {
-                        tr {
-                            th { "Name" }
-                            th { "Size" }
-                            th { "Uploaded" }
-                        }
-                    }
-                    tbody {
-                        @if let Some((parent_key, _)) = readable_key_prefix.trim_end_matches('/').rsplit_once('/') {
-                            tr {
-                                td[colspan = "3"] {
-                                    "📁 "
-                                    a[href = format!("/{parent_key}/")] {
-                                        "../"
-                                    }
-                                }
-                            }
-                        }
-                        @for (entry_type, key) in entries.into_iter() {
-                            tr {
-                                @if let EntryType::File { size, uploaded } = entry_type {
-                                    td {
-                                        "📄 "
-                                        a[href = format!("/{key}")] {
-                                            @key.strip_prefix(key_prefix).expect("must be a prefix")
-                                        }
-                                    }
-                                    td {
-                                        @humansize::format_size(*size, file_size_format_options)
-                                    }
-                                    td {
-                                        @uploaded.format(DATE_FORMAT).to_string()
-                                    }
-                                } else {
-                                    td[colspan = "3"] {
-                                        "📁 "
-                                        a[href = format!("/{key}")] {
-                                            @key.strip_prefix(key_prefix).expect("must be a prefix")
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+pub fn href(key: &str) -> String {
+    format!("/{}", key.split('/').map(urlencoding::encode).join("/"))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn extension(key: &str) -> Option<String> {
+    let (_, extension) = key.rsplit('/').next()?.rsplit_once('.')?;
+    (!extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
+
+fn prefixes(keys: &[String]) -> Vec<&str> {
+    let mut prefixes: Vec<&str> = keys
+        .iter()
+        .flat_map(|key| {
+            key.match_indices('/')
+                .map(|(index, _)| &key[..=index])
+                .chain(std::iter::once(""))
+        })
+        .collect();
+    prefixes.sort_unstable();
+    prefixes.dedup();
+    prefixes
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn random_hex(length: usize) -> String {
+    let bytes: Vec<u8> = (0..length)
+        .map(|_| (worker::js_sys::Math::random() * 256.0) as u8)
+        .collect();
+    hex(&bytes)
+}
+
+fn unhex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
     }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn redirect(location: &str, status: u16) -> worker::Result<worker::Response> {
+    let mut headers = worker::Headers::new();
+    headers.set("location", location)?;
+    Ok(worker::Response::empty()?
+        .with_status(status)
+        .with_headers(headers))
+}
+
+fn redirect_to_path(
+    request: &worker::Request,
+    path: &str,
+    status: u16,
+) -> worker::Result<worker::Response> {
+    let location = match request.url()?.query() {
+        Some(query) => format!("{}?{query}", href(path)),
+        None => href(path),
+    };
+    redirect(&location, status)
 }
 
+#[cfg(feature = "entrypoint")]
 #[worker::event(start)]
 pub fn main() {
-    console_error_panic_hook::set_once();
+    reporting::install_panic_hook();
 }
 
-#[worker::event(fetch)]
-pub async fn main(
+#[cfg(feature = "entrypoint")]
+mod _worker_fetch {
+    use worker::worker_sys::web_sys;
+    use worker::{wasm_bindgen, wasm_bindgen_futures};
+
+    #[wasm_bindgen::prelude::wasm_bindgen]
+    pub async fn fetch(
+        request: web_sys::Request,
+        environment: worker::Env,
+        context: worker::worker_sys::Context,
+    ) -> web_sys::Response {
+        match super::handle(
+            worker::Request::from(request),
+            environment,
+            worker::Context::new(context),
+        )
+        .await
+        .map(web_sys::Response::from)
+        .and_then(encode_body)
+        {
+            Ok(response) => response,
+            Err(err) => {
+                worker::console_error!("{}", &err);
+                panic!("{}", err)
+            }
+        }
+    }
+
+    fn encode_body(response: web_sys::Response) -> worker::Result<web_sys::Response> {
+        if !response.headers().has("content-encoding")? {
+            return Ok(response);
+        }
+        let mut init = web_sys::ResponseInit::new();
+        init.status(response.status()).headers(&response.headers());
+        worker::js_sys::Reflect::set(&init, &"encodeBody".into(), &"manual".into())?;
+        Ok(web_sys::Response::new_with_opt_readable_stream_and_init(
+            response.body().as_ref(),
+            &init,
+        )?)
+    }
+}
+
+async fn guard(
+    guards: &[Guard],
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &config::Config,
+) -> worker::Result<Option<worker::Response>> {
+    for guard in guards {
+        if let Some(response) = guard(request, environment, config).await? {
+            return Ok(Some(response));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn handle(
     request: worker::Request,
     environment: worker::Env,
-    _context: worker::Context,
+    context: worker::Context,
+) -> worker::Result<worker::Response> {
+    let mut config = match config::Config::load(&environment).await {
+        Ok(config) => config,
+        Err(err) => {
+            worker::console_error!("{}", &err);
+            let config = config::Config::default();
+            return errors::for_request(
+                &request,
+                &environment,
+                &config,
+                500,
+                "Internal Server Error",
+            )
+            .await;
+        }
+    };
+    config.language = i18n::negotiate(&request, &config)?;
+    let started = worker::Date::now().as_millis();
+    let request_id = logging::request_id(&request)?;
+    let reporter = reporting::Reporter::new(&config, &request, &request_id)?;
+    let original_request = request.clone()?;
+    let response = match respond(request, &environment, &config, &context).await {
+        Ok(response) => response,
+        Err(err) => {
+            logging::error(&config, &request_id, &err);
+            if let Some(reporter) = reporter {
+                let message = err.to_string();
+                context.wait_until(async move {
+                    if let Err(err) = reporter.report(&message).await {
+                        worker::console_error!("failed to report an error: {err}");
+                    }
+                });
+            }
+            errors::for_request(
+                &original_request,
+                &environment,
+                &config,
+                500,
+                "Internal Server Error",
+            )
+            .await?
+        }
+    };
+    let response = headers_file::apply(&original_request, &environment, &config, response).await?;
+    let response = cors::apply(&original_request, &config, response)?;
+    let response = security::apply(&config, response)?;
+    let mut headers = response.headers().clone();
+    headers.set("x-request-id", &request_id)?;
+    let response = response.with_headers(headers);
+
+    if let Err(err) = analytics::record(&original_request, &environment, &response) {
+        logging::error(&config, &request_id, &err);
+    }
+    if response.status_code() == 404 {
+        let event = webhooks::Event::NotFound;
+        if let Err(err) = webhooks::notify(&config, &context, &original_request, event) {
+            logging::error(&config, &request_id, &err);
+        }
+    }
+    if let Err(err) = logging::request(&config, &original_request, &response, &request_id, started)
+    {
+        logging::error(&config, &request_id, &err);
+    }
+    Ok(response)
+}
+
+async fn respond(
+    request: worker::Request,
+    environment: &worker::Env,
+    config: &config::Config,
+    context: &worker::Context,
 ) -> worker::Result<worker::Response> {
-    if request.method() != worker::Method::Get {
-        return worker::Response::error("Bad Request", 400);
+    if let Some(response) = cors::preflight(&request, config)? {
+        return Ok(response);
+    }
+    if let Some(response) = guard(&REQUEST_GUARDS, &request, environment, config).await? {
+        return Ok(response);
     }
 
-    let bucket = environment.bucket("BUCKET")?;
+    if request.path() == graphql::PATH
+        && config.graphql
+        && matches!(request.method(), worker::Method::Get | worker::Method::Post)
+    {
+        if let Some(response) = guard(&LISTING_GUARDS, &request, environment, config).await? {
+            return Ok(response);
+        }
+        return graphql::handle(request, environment, config).await;
+    }
 
-    let file_size_format_options =
-        humansize::FormatSizeOptions::from(humansize::DECIMAL).decimal_places(2);
+    if request.path() == trash::PATH
+        && config.trash_prefix.is_some()
+        && matches!(request.method(), worker::Method::Get | worker::Method::Post)
+    {
+        return trash::handle(&request, environment, config).await;
+    }
 
-    let path = request.path();
+    if request.method() == worker::Method::Post
+        && !request.path().ends_with('/')
+        && turnstile::enabled(config)
+    {
+        return turnstile::verify(request, environment, config).await;
+    }
+
+    match request.method() {
+        worker::Method::Get | worker::Method::Head => {}
+        worker::Method::Delete if config.allow_delete && !request.path().ends_with('/') => {}
+        worker::Method::Options => return methods::options(&request, config),
+        _ => return methods::not_allowed(&request, environment, config).await,
+    }
+    if request.path() == health::PATH {
+        return health::handle(environment, config).await;
+    }
+    if request.path() == openapi::PATH {
+        return openapi::handle(&request);
+    }
+
+    let path = match redirects::resolve(&request, environment, config).await? {
+        Some(redirects::Resolution::Redirect { location, status }) => {
+            return redirect(&location, status);
+        }
+        Some(redirects::Resolution::Rewrite { path }) => path,
+        None => request.path(),
+    };
     let path =
         urlencoding::decode(&path).map_err(|err| worker::Error::RustError(err.to_string()))?;
-    let key_prefix = path.trim_start_matches('/');
-    let readable_key_prefix = if key_prefix.is_empty() {
-        "/"
-    } else {
-        key_prefix
+    let path = path.trim_start_matches('/');
+
+    if mounts::is_mount(config, path) {
+        return redirect_to_path(&request, &format!("{path}/"), 301);
+    }
+    let Some(mount) = mounts::resolve(
+        environment,
+        config,
+        request.url()?.host_str().unwrap_or_default(),
+        path,
+    )?
+    else {
+        return listing::unmounted(environment, config, path).await;
     };
 
-    if readable_key_prefix.ends_with('/') {
-        let list_response = bucket
-            .list()
-            .delimiter("/")
-            .prefix(key_prefix)
-            .execute()
-            .await?;
-
-        let mut entries: Vec<(EntryType, String)> = list_response
-            .delimited_prefixes()
-            .into_iter()
-            .map(|key| (EntryType::Directory, key))
-            .chain(list_response.objects().into_iter().map(|object| {
-                (
-                    EntryType::File {
-                        size: object.size() as u32,
-                        uploaded: chrono::NaiveDateTime::from_timestamp_millis(
-                            object.uploaded().as_millis() as i64,
-                        )
-                        .expect("must be valid")
-                        .and_utc(),
-                    },
-                    object.key(),
-                )
-            }))
-            .collect();
-        if entries.is_empty() {
-            worker::Response::error("Not Found.", 404)
-        } else {
-            entries.sort();
-            let mut headers = worker::Headers::new();
-            headers.set("content-type", "text/html")?;
-            Ok(worker::Response::ok(
-                EntryList {
-                    key_prefix,
-                    readable_key_prefix,
-                    entries,
-                    file_size_format_options,
-                }
-                .to_string(),
-            )?
-            .with_headers(headers))
+    if trash::contains(config, &mount.key(path)) {
+        return errors::response(environment, config, Some(&mount), 404, "Not Found").await;
+    }
+    if request.method() == worker::Method::Delete {
+        return trash::delete(&request, environment, config, &mount, path).await;
+    }
+
+    if path.is_empty() || path.ends_with('/') {
+        if let Some(response) = guard(&LISTING_GUARDS, &request, environment, config).await? {
+            return Ok(response);
+        }
+        if feed::requested(&request.url()?) {
+            return feed::handle(&request, config, &mount, path).await;
+        }
+        if checksums::requested(&request.url()?) {
+            return checksums::handle(&request, config, &mount, path).await;
+        }
+        if playlist::requested(&request.url()?) {
+            return playlist::handle(&request, config, &mount, path).await;
         }
+        if du::requested(&request.url()?) {
+            return du::handle(environment, config, &mount, path).await;
+        }
+        listing::handle(&request, environment, config, context, &mount, path).await
     } else {
-        match bucket.get(key_prefix).execute().await? {
-            Some(object) => {
-                worker::Response::from_stream(object.body().expect("must be available").stream()?)
-            }
-            None => worker::Response::error("Not Found", 404),
+        if let Some(response) =
+            files::generated(&request, environment, config, context, &mount, path).await?
+        {
+            return Ok(response);
         }
+        if let Some(response) = guard(&FILE_GUARDS, &request, environment, config).await? {
+            return Ok(response);
+        }
+        files::handle(&request, environment, config, context, &mount, path).await
+    }
+}
+
+#[cfg(feature = "entrypoint")]
+#[worker::event(queue)]
+pub async fn main(
+    message_batch: worker::MessageBatch<notifications::Notification>,
+    environment: worker::Env,
+    _context: worker::Context,
+) -> worker::Result<()> {
+    notifications::consume(message_batch, &environment).await
+}
+
+#[cfg(feature = "entrypoint")]
+#[worker::event(scheduled)]
+pub async fn main(
+    _event: worker::ScheduledEvent,
+    environment: worker::Env,
+    _context: worker::ScheduleContext,
+) {
+    if let Err(err) = trash::purge(&environment).await {
+        worker::console_error!("failed to purge the trash: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(key: &str) {
+        let encoded = href(key);
+        assert!(!encoded[1..].contains(['#', '?', ' ']), "{encoded}");
+        assert_eq!(
+            urlencoding::decode(&encoded).unwrap(),
+            format!("/{key}"),
+            "{encoded}"
+        );
+    }
+
+    #[test]
+    fn encodes_spaces() {
+        round_trip("my file.txt");
+        assert_eq!(href("my file.txt"), "/my%20file.txt");
+    }
+
+    #[test]
+    fn encodes_percent_signs() {
+        round_trip("100%.txt");
+        round_trip("already%20encoded");
+        assert_eq!(href("100%.txt"), "/100%25.txt");
+    }
+
+    #[test]
+    fn encodes_fragment_and_query_delimiters() {
+        round_trip("issue #1.md");
+        round_trip("what?.txt");
+        round_trip("a?b=c&d#e");
+    }
+
+    #[test]
+    fn encodes_unicode() {
+        round_trip("zażółć gęślą jaźń.pdf");
+        round_trip("日本語/ファイル.txt");
+        round_trip("emoji 🎉.png");
+    }
+
+    #[test]
+    fn keeps_nested_segments() {
+        round_trip("a/b c/d#e/");
+        round_trip("dir/");
+        assert_eq!(href("a b/c d/"), "/a%20b/c%20d/");
     }
 }
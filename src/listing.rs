@@ -0,0 +1,1395 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use futures_util::StreamExt;
+use itertools::Itertools;
+
+use crate::EntryType;
+
+const ROWS_PLACEHOLDER: &str = "<!-- rows -->";
+
+const FOOTER_PLACEHOLDER: &str = "<!-- footer -->";
+
+const ROWS_PER_CHUNK: usize = 1000;
+
+pub type Objects = HashMap<String, crate::store::Object>;
+
+const FILTER_SCRIPT: &str = r#"
+const filter = document.getElementById('filter');
+filter.addEventListener('input', () => {
+    const query = filter.value.trim().toLowerCase();
+    for (const row of document.querySelectorAll('tbody tr[data-name]')) {
+        row.hidden = !row.dataset.name.toLowerCase().includes(query);
+    }
+});
+"#;
+
+const LOAD_MORE_SCRIPT: &str = r#"
+document.addEventListener('click', async (event) => {
+    const link = event.target.closest('tr.more a');
+    if (!link) return;
+    event.preventDefault();
+    const row = link.closest('tr');
+    link.removeAttribute('href');
+    const response = await fetch(link.dataset.partial);
+    row.insertAdjacentHTML('afterend', await response.text());
+    row.remove();
+    filter.dispatchEvent(new Event('input'));
+});
+"#;
+
+const TREE_SCRIPT: &str = r#"
+document.addEventListener('toggle', async (event) => {
+    const details = event.target;
+    if (!details.open || details.dataset.loaded) return;
+    details.dataset.loaded = 'true';
+    const response = await fetch(details.dataset.href + '?format=json');
+    const listing = await response.json();
+    const list = document.createElement('ul');
+    for (const entry of listing.entries) {
+        const item = document.createElement('li');
+        const href = details.dataset.href + entry.name.split('/').map(encodeURIComponent).join('/');
+        if (entry.type === 'directory') {
+            const child = document.createElement('details');
+            const summary = document.createElement('summary');
+            child.dataset.href = href;
+            summary.textContent = '📁 ' + entry.name;
+            child.append(summary);
+            item.append(child);
+        } else {
+            const link = document.createElement('a');
+            link.href = href;
+            link.textContent = entry.name;
+            item.append('📄 ', link);
+        }
+        list.append(item);
+    }
+    details.append(list);
+}, true);
+"#;
+
+markup::define! {
+    EntryList<'a, Rows: markup::Render, Footer: markup::Render>(
+        readable_key_prefix: &'a str,
+        downloads: bool,
+        checksums: bool,
+        metadata_columns: &'a [String],
+        embed: bool,
+        font_url: Option<&'a str>,
+        i18n: &'a crate::i18n::Translator,
+        highlights: Option<Highlights<'a>>,
+        playlist: Option<&'a str>,
+        filter: Option<&'a str>,
+        rows: Rows,
+        footer: Footer
+    ) {
+        @markup::doctype()
+        html {
+            head {
+                meta[charset = "utf-8"] {}
+                title { @readable_key_prefix }
+                link[rel = "alternate", type = "application/atom+xml", href = "?feed=atom"] {}
+                @if *embed {
+                    base[target = "_parent"] {}
+                }
+                style {
+                    @crate::fonts::face(*font_url)
+                    "html { font-family: 'Inconsolata', monospace; }"
+                    "body { padding: 1em; }"
+                    "* { margin: 0; padding: 0; }"
+                    "header { margin-bottom: 2em; }"
+                    "table { margin-left: 1em; }"
+                    "td, th { padding: 0.25em; max-width: 300px; }"
+                    "thead { background-color: #eee; }"
+                    "tfoot { color: #666; }"
+                    "th { min-width: 100px; font-size: 1.1em; }"
+                    "section { margin: 0 0 2em 1em; }"
+                    "h2 { font-size: 1.2em; margin-bottom: 0.5em; }"
+                    "li { margin-left: 1.5em; padding: 0.1em; }"
+                    "form { margin: 1em 0 0 1em; }"
+                    "input { font: inherit; padding: 0.1em 0.25em; }"
+                    @if *embed {
+                        "body { padding: 0; }"
+                        "table { margin-left: 0; }"
+                        "td, th { padding: 0.1em 0.25em; }"
+                        "th { min-width: 0; font-size: 1em; }"
+                    }
+                }
+            }
+            body {
+                @if !*embed {
+                    header {
+                        h1 {
+                            @readable_key_prefix
+                        }
+                        @if let Some(playlist) = playlist {
+                            p {
+                                a[href = playlist] { @i18n.text("▶ Play all") }
+                            }
+                        }
+                        form[method = "get"] {
+                            input[
+                                type = "search",
+                                id = "filter",
+                                name = "filter",
+                                value = filter.unwrap_or_default(),
+                                placeholder = i18n.text("Filter"),
+                                "aria-label" = i18n.text("Filter")
+                            ] {}
+                        }
+                    }
+                }
+                @if let Some(highlights) = highlights {
+                    @highlights
+                }
+                table {
+                    thead {
+                        tr {
+                            th { @i18n.text("Name") }
+                            th { @i18n.text("Size") }
+                            th { @i18n.text("Uploaded") }
+                            @if *downloads {
+                                th { @i18n.text("Downloads") }
+                            }
+                            @if *checksums {
+                                th { @i18n.text("Checksum") }
+                            }
+                            @for column in metadata_columns.iter() {
+                                th { @column }
+                            }
+                        }
+                    }
+                    tbody {
+                        @if let Some((parent_key, _)) = readable_key_prefix.trim_end_matches('/').rsplit_once('/') {
+                            tr {
+                                td[colspan = 3 + usize::from(*downloads) + usize::from(*checksums) + metadata_columns.len()] {
+                                    "📁 "
+                                    a[href = crate::href(&format!("{parent_key}/"))] {
+                                        "../"
+                                    }
+                                }
+                            }
+                        }
+                        @rows
+                    }
+                    tfoot {
+                        @footer
+                    }
+                }
+                @if !*embed {
+                    script {
+                        @markup::raw(FILTER_SCRIPT)
+                        @markup::raw(LOAD_MORE_SCRIPT)
+                    }
+                }
+            }
+        }
+    }
+
+    Summary<'a>(
+        totals: &'a Totals,
+        prefix: Option<crate::du::Usage>,
+        columns: usize,
+        file_size_format_options: humansize::FormatSizeOptions,
+        i18n: &'a crate::i18n::Translator
+    ) {
+        tr {
+            td[colspan = columns] {
+                @i18n.count("1 file", "{count} files", totals.files)
+                ", "
+                @i18n.count("1 directory", "{count} directories", totals.directories)
+                ", "
+                @humansize::format_size(totals.size, *file_size_format_options)
+                @if let Some(prefix) = prefix {
+                    " ("
+                    @i18n
+                        .text("{size} in {files} below this directory")
+                        .replace("{size}", &humansize::format_size(prefix.size, *file_size_format_options))
+                        .replace("{files}", &i18n.count("1 file", "{count} files", prefix.count))
+                    ")"
+                }
+            }
+        }
+    }
+
+    LoadMore<'a>(href: Option<String>, columns: usize, i18n: &'a crate::i18n::Translator) {
+        @if let Some(href) = href {
+            tr.more {
+                td[colspan = columns] {
+                    a[href = href, "data-partial" = format!("{href}&partial=rows")] {
+                        @i18n.text("Load more")
+                    }
+                }
+            }
+        }
+    }
+
+    Highlights<'a>(
+        recent: &'a [(String, chrono::DateTime<chrono::Utc>)],
+        popular: &'a [(String, u64)],
+        dates: &'a crate::dates::Dates,
+        i18n: &'a crate::i18n::Translator
+    ) {
+        @if !recent.is_empty() {
+            section {
+                h2 { @i18n.text("Recently added") }
+                ul {
+                    @for (path, uploaded) in recent.iter() {
+                        li {
+                            @HighlightLink { path, i18n }
+                            " – "
+                            @crate::dates::Timestamp { dates, at: *uploaded }
+                        }
+                    }
+                }
+            }
+        }
+        @if !popular.is_empty() {
+            section {
+                h2 { @i18n.text("Most downloaded") }
+                ul {
+                    @for (path, count) in popular.iter() {
+                        li {
+                            @HighlightLink { path, i18n }
+                            " – "
+                            @i18n.count("1 download", "{count} downloads", *count)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    HighlightLink<'a>(path: &'a str, i18n: &'a crate::i18n::Translator) {
+        @let (directory, name) = match path.rsplit_once('/') {
+            Some((directory, name)) => (format!("{directory}/"), name),
+            None => (String::new(), *path),
+        };
+        a[href = crate::href(path)] {
+            @name
+        }
+        @i18n.text(" in ")
+        a[href = crate::href(&directory)] {
+            "/"
+            @directory
+        }
+    }
+
+    Tree<'a>(
+        readable_key_prefix: &'a str,
+        key_prefix: &'a str,
+        path_prefix: &'a str,
+        entries: &'a [(EntryType, String)],
+        font_url: Option<&'a str>
+    ) {
+        @markup::doctype()
+        html {
+            head {
+                meta[charset = "utf-8"] {}
+                title { @readable_key_prefix }
+                style {
+                    @crate::fonts::face(*font_url)
+                    "html { font-family: 'Inconsolata', monospace; }"
+                    "body { padding: 1em; }"
+                    "* { margin: 0; padding: 0; }"
+                    "header { margin-bottom: 2em; }"
+                    "ul { list-style: none; margin-left: 1.5em; }"
+                    "li { padding: 0.1em; }"
+                    "summary { cursor: pointer; }"
+                }
+            }
+            body {
+                header {
+                    h1 {
+                        @readable_key_prefix
+                    }
+                }
+                ul {
+                    @for (entry_type, key) in entries.iter() {
+                        @let name = key.strip_prefix(key_prefix).expect("must be a prefix");
+                        @let href = crate::href(&format!("{path_prefix}{name}"));
+                        li {
+                            @if let EntryType::File { .. } = entry_type {
+                                "📄 "
+                                a[href = &href] {
+                                    @name
+                                }
+                            } else {
+                                details["data-href" = &href] {
+                                    summary {
+                                        "📁 "
+                                        @name
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                script {
+                    @markup::raw(TREE_SCRIPT)
+                }
+            }
+        }
+    }
+
+    Gallery<'a>(
+        readable_key_prefix: &'a str,
+        key_prefix: &'a str,
+        path_prefix: &'a str,
+        entries: &'a [(EntryType, String)],
+        config: &'a crate::config::Config
+    ) {
+        @markup::doctype()
+        html {
+            head {
+                meta[charset = "utf-8"] {}
+                title { @readable_key_prefix }
+                style {
+                    @crate::fonts::face(config.font_url.as_deref())
+                    "html { font-family: 'Inconsolata', monospace; }"
+                    "body { padding: 1em; }"
+                    "* { margin: 0; padding: 0; }"
+                    "header { margin-bottom: 2em; }"
+                    "ul { list-style: none; margin: 0 0 1em 1em; }"
+                    "li { padding: 0.1em; }"
+                    "section { display: flex; flex-wrap: wrap; gap: 1em; margin-left: 1em; }"
+                    @format!("figure {{ width: {}px; }}", config.thumbnail_width)
+                    @format!(
+                        "img {{ width: {}px; height: {}px; object-fit: cover; background-color: #eee; }}",
+                        config.thumbnail_width,
+                        config.thumbnail_height
+                    )
+                    "figcaption { overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }"
+                }
+            }
+            body {
+                header {
+                    h1 {
+                        @readable_key_prefix
+                    }
+                }
+                ul {
+                    @if let Some((parent_key, _)) = readable_key_prefix.trim_end_matches('/').rsplit_once('/') {
+                        li {
+                            "📁 "
+                            a[href = format!("{}?view=gallery", crate::href(&format!("{parent_key}/")))] {
+                                "../"
+                            }
+                        }
+                    }
+                    @for (_, key) in entries.iter().filter(|(entry_type, _)| *entry_type == EntryType::Directory) {
+                        @let name = key.strip_prefix(key_prefix).expect("must be a prefix");
+                        li {
+                            "📁 "
+                            a[href = format!("{}?view=gallery", crate::href(&format!("{path_prefix}{name}")))] {
+                                @name
+                            }
+                        }
+                    }
+                }
+                section {
+                    @for (_, key) in entries.iter().filter(|(entry_type, _)| *entry_type != EntryType::Directory) {
+                        @let name = key.strip_prefix(key_prefix).expect("must be a prefix");
+                        @if crate::thumbnails::is_image(config, name) {
+                            @let path = format!("{path_prefix}{name}");
+                            figure {
+                                a[href = crate::href(&path)] {
+                                    img[
+                                        src = crate::thumbnails::url(config, &path),
+                                        "data-original" = crate::href(&path),
+                                        alt = name,
+                                        loading = "lazy",
+                                        onerror = "this.onerror = null; this.src = this.dataset.original;"
+                                    ] {}
+                                }
+                                figcaption[title = name] {
+                                    @name
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    EntryRows<'a>(
+        key_prefix: &'a str,
+        path_prefix: &'a str,
+        entries: &'a [(EntryType, String)],
+        file_size_format_options: humansize::FormatSizeOptions,
+        dates: &'a crate::dates::Dates,
+        i18n: &'a crate::i18n::Translator,
+        downloads: Option<&'a HashMap<String, u64>>,
+        sizes: Option<&'a HashMap<String, crate::du::Usage>>,
+        checksums: bool,
+        metadata_columns: &'a [String],
+        objects: &'a Objects
+    ) {
+        @let extra_columns = usize::from(downloads.is_some()) + usize::from(*checksums) + metadata_columns.len();
+        @for (entry_type, key) in entries.iter() {
+            @let name = key.strip_prefix(key_prefix).expect("must be a prefix");
+            tr["data-name" = name] {
+                @if let EntryType::File { size, uploaded } = entry_type {
+                    td {
+                        "📄 "
+                        a[href = crate::href(&format!("{path_prefix}{name}"))] {
+                            @name
+                        }
+                    }
+                    td {
+                        @humansize::format_size(*size, file_size_format_options)
+                    }
+                    td {
+                        @crate::dates::Timestamp { dates, at: *uploaded }
+                    }
+                    @if let Some(downloads) = downloads {
+                        td {
+                            @downloads.get(name).copied().unwrap_or_default()
+                        }
+                    }
+                    @let object = objects.get(name);
+                    @if *checksums {
+                        td {
+                            @if let Some((algorithm, checksum)) = object.and_then(|object| object.checksums.last()) {
+                                span[title = format!("{algorithm}:{checksum}")] {
+                                    @algorithm
+                                    ":"
+                                    @checksum.get(..12).unwrap_or(checksum)
+                                    "…"
+                                }
+                            }
+                        }
+                    }
+                    @for column in metadata_columns.iter() {
+                        td {
+                            @object
+                                .and_then(|object| object.custom_metadata.iter().find(|(name, _)| name == column))
+                                .map(|(_, value)| value.as_str())
+                                .unwrap_or_default()
+                        }
+                    }
+                } else if let Some(usage) = sizes.and_then(|sizes| sizes.get(name)) {
+                    td {
+                        "📁 "
+                        a[href = crate::href(&format!("{path_prefix}{name}"))] {
+                            @name
+                        }
+                    }
+                    td[title = i18n.count("1 file", "{count} files", usage.count)] {
+                        @humansize::format_size(usage.size, file_size_format_options)
+                    }
+                    td[colspan = 1 + extra_columns] {}
+                } else {
+                    td[colspan = 3 + extra_columns] {
+                        "📁 "
+                        a[href = crate::href(&format!("{path_prefix}{name}"))] {
+                            @name
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Totals {
+    pub files: u64,
+    pub directories: u64,
+    pub size: u64,
+}
+
+impl Totals {
+    fn add(&mut self, entries: &[(EntryType, String)]) {
+        for (entry_type, _) in entries {
+            match entry_type {
+                EntryType::File { size, .. } => {
+                    self.files += 1;
+                    self.size += size;
+                }
+                EntryType::Directory => self.directories += 1,
+            }
+        }
+    }
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    context: &worker::Context,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let readable_key_prefix = if path.is_empty() { "/" } else { path };
+    let dates = crate::dates::Dates::from_config(config);
+    let i18n = crate::i18n::Translator::from_config(config);
+    let store = mount.store.clone();
+    let key = mount.key(path);
+    let key_prefix = key.as_str();
+
+    let url = request.url()?;
+    let file_size_format_options = crate::sizes::options(config, Some(&url));
+    let sorting = crate::sorting::Sorting::from_request(config, Some(&url));
+    let show_all = url
+        .query_pairs()
+        .any(|(name, value)| name == "all" && value != "0");
+    let embed = url
+        .query_pairs()
+        .any(|(name, value)| name == "embed" && value != "0");
+    let recursive = url
+        .query_pairs()
+        .any(|(name, value)| name == "recursive" && value != "0");
+    let json = url
+        .query_pairs()
+        .any(|(name, value)| name == "format" && value == "json");
+    let tree = !json
+        && url
+            .query_pairs()
+            .any(|(name, value)| name == "view" && value == "tree");
+    let gallery = !json
+        && url
+            .query_pairs()
+            .any(|(name, value)| name == "view" && value == "gallery");
+    let start = url
+        .query_pairs()
+        .find(|(name, _)| name == "cursor")
+        .map(|(_, value)| value.into_owned());
+    let partial = url
+        .query_pairs()
+        .any(|(name, value)| name == "partial" && value == "rows");
+    let load_more = partial || config.listing_load_more;
+    if show_all && crate::auth::identity(request, config)?.is_none() {
+        return crate::auth::challenge();
+    }
+    let listing_cache = if show_all {
+        None
+    } else {
+        crate::listing_cache::ListingCache::from_config(request, config, key_prefix)?
+    };
+    if let Some(listing_cache) = &listing_cache {
+        if let Some(response) = listing_cache.get().await? {
+            let mut headers = response.headers().clone();
+            headers.set("x-cache", "HIT")?;
+            return crate::etag::conditional(request, response.with_headers(headers));
+        }
+    }
+
+    if !show_all
+        && store
+            .head(&format!("{key_prefix}.noindex"))
+            .await?
+            .is_some()
+    {
+        return match config.noindex_status {
+            403 => {
+                crate::errors::response(environment, config, Some(mount), 403, "Forbidden").await
+            }
+            _ => crate::errors::response(environment, config, Some(mount), 404, "Not Found").await,
+        };
+    }
+
+    let downloads = crate::counters::counts(environment, config, mount, path, recursive).await?;
+    let query = url
+        .query_pairs()
+        .find(|(name, _)| name == "filter")
+        .map(|(_, value)| value.into_owned());
+    let filter =
+        crate::filter::EntryFilter::from_config(config, show_all).matching(query.as_deref());
+    let highlights = match config.highlights {
+        Some(limit)
+            if path.is_empty()
+                && start.is_none()
+                && !embed
+                && !recursive
+                && !json
+                && !tree
+                && !gallery =>
+        {
+            Some(crate::highlights::load(environment, config, limit, mount, &filter).await?)
+        }
+        _ => None,
+    };
+    let mut cursor = None;
+    let include_metadata = !config.metadata_columns.is_empty();
+    let mut objects = Objects::new();
+    let mut entries = if recursive {
+        let files = crate::walk::files(mount, path, &filter).await?;
+        objects.extend(objects_by_name(
+            files.iter().map(|(_, object)| object),
+            key_prefix,
+        ));
+        files
+            .into_iter()
+            .map(|(file, object)| {
+                (
+                    EntryType::File {
+                        size: object.size,
+                        uploaded: object.uploaded,
+                    },
+                    format!("{key_prefix}{}", &file[path.len()..]),
+                )
+            })
+            .collect()
+    } else {
+        let list_response =
+            list(store.as_ref(), key_prefix, start.clone(), include_metadata).await?;
+        cursor = list_response.cursor.clone();
+        objects.extend(objects_by_name(&list_response.objects, key_prefix));
+        let mut entries = list_entries(&list_response, key_prefix, &filter);
+        if start.is_none() {
+            entries.extend(
+                crate::mounts::children(config, path)
+                    .into_iter()
+                    .map(|name| (EntryType::Directory, format!("{key_prefix}{name}"))),
+            );
+        }
+        entries
+    };
+    let paged = !(json || tree || gallery)
+        && sorting.in_key_order()
+        && crate::mounts::children(config, path).is_empty();
+    if !paged {
+        while let Some(next) = cursor.take() {
+            let list_response =
+                list(store.as_ref(), key_prefix, Some(next), include_metadata).await?;
+            objects.extend(objects_by_name(&list_response.objects, key_prefix));
+            entries.extend(list_entries(&list_response, key_prefix, &filter));
+            cursor = list_response.cursor;
+        }
+    }
+    if entries.is_empty() && cursor.is_none() && filter.query().is_none() && start.is_none() {
+        if let Some(file) = path.strip_suffix('/').filter(|file| !file.is_empty()) {
+            if store.head(&mount.key(file)).await?.is_some() {
+                return crate::redirect_to_path(request, file, 301);
+            }
+        }
+        return crate::errors::response(environment, config, Some(mount), 404, "Not Found").await;
+    }
+    let disk_usage = config
+        .show_directory_sizes
+        .then(|| crate::du::DiskUsage::from_config(environment, config));
+    let sizes = match &disk_usage {
+        Some(disk_usage) => Some(
+            disk_usage
+                .directories(Some(context), mount, path, &entries)
+                .await?,
+        ),
+        None => None,
+    };
+
+    let prefix_usage = match &disk_usage {
+        Some(disk_usage) if !json && !tree && !gallery => {
+            disk_usage.prefix(context, mount, path).await?
+        }
+        _ => None,
+    };
+    let show_checksums = config.show_checksums;
+    let metadata_columns = config.metadata_columns.as_slice();
+    let columns =
+        3 + usize::from(downloads.is_some()) + usize::from(show_checksums) + metadata_columns.len();
+    let mut totals = Totals::default();
+    totals.add(&entries);
+    let playlist = entries
+        .iter()
+        .any(|(entry_type, key)| {
+            matches!(entry_type, EntryType::File { .. }) && crate::playlist::is_audio(config, key)
+        })
+        .then_some(if recursive {
+            "?playlist=m3u&recursive=1"
+        } else {
+            "?playlist=m3u"
+        });
+    let etag = |entries: &[(EntryType, String)]| {
+        let mut variant = format!("{}\0{file_size_format_options:?}\0", config.language);
+        if let Some(usage) = prefix_usage {
+            variant.push_str(&format!("{} {}\0", usage.size, usage.count));
+        }
+        for (entry_type, key) in entries {
+            let name = key.strip_prefix(key_prefix).unwrap_or(key);
+            if let EntryType::File { uploaded, .. } = entry_type {
+                variant.push_str(&dates.display(*uploaded));
+            }
+            if let Some(count) = downloads.as_ref().and_then(|downloads| downloads.get(name)) {
+                variant.push_str(&format!(" {count}"));
+            }
+            if let Some(usage) = sizes.as_ref().and_then(|sizes| sizes.get(name)) {
+                variant.push_str(&format!(" {} {}", usage.size, usage.count));
+            }
+            variant.push('\0');
+        }
+        crate::etag::for_entries(entries, &variant)
+    };
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/html")?;
+    crate::robots::tag(&config.robots_tag_listings, &mut headers)?;
+    crate::i18n::write_headers(config, &mut headers)?;
+    if embed {
+        headers.set(
+            "content-security-policy",
+            &crate::security::with_frame_ancestors(config, &config.embed_frame_ancestors),
+        )?;
+    }
+    crate::security::page(config, &mut headers)?;
+    let mut response = if json {
+        sorting.sort(&mut entries);
+        headers.set("content-type", "application/json")?;
+        headers.set("etag", &etag(&entries))?;
+        worker::Response::ok(
+            serde_json::json!({
+                "path": readable_key_prefix,
+                "entries": entries_json(
+                    &entries,
+                    key_prefix,
+                    downloads.as_ref(),
+                    sizes.as_ref(),
+                    &objects,
+                ),
+            })
+            .to_string(),
+        )?
+        .with_headers(headers)
+    } else if gallery {
+        sorting.sort(&mut entries);
+        headers.set("etag", &etag(&entries))?;
+        worker::Response::ok(
+            Gallery {
+                readable_key_prefix,
+                key_prefix,
+                path_prefix: path,
+                entries: &entries,
+                config,
+            }
+            .to_string(),
+        )?
+        .with_headers(headers)
+    } else if tree {
+        sorting.sort(&mut entries);
+        headers.set("etag", &etag(&entries))?;
+        worker::Response::ok(
+            Tree {
+                readable_key_prefix,
+                key_prefix,
+                path_prefix: path,
+                entries: &entries,
+                font_url: config.font_url.as_deref(),
+            }
+            .to_string(),
+        )?
+        .with_headers(headers)
+    } else if partial {
+        sorting.sort(&mut entries);
+        let rows = EntryRows {
+            key_prefix,
+            path_prefix: path,
+            entries: &entries,
+            file_size_format_options,
+            dates: &dates,
+            i18n: &i18n,
+            downloads: downloads.as_ref(),
+            sizes: sizes.as_ref(),
+            checksums: show_checksums,
+            metadata_columns,
+            objects: &objects,
+        };
+        let more = LoadMore {
+            href: cursor.as_deref().map(|cursor| page_href(&url, cursor)),
+            columns,
+            i18n: &i18n,
+        };
+        worker::Response::ok(format!("{rows}{more}"))?.with_headers(headers)
+    } else if let (Some(cursor), false) = (cursor.clone(), load_more) {
+        sorting.sort(&mut entries);
+        let page = EntryList {
+            readable_key_prefix,
+            downloads: downloads.is_some(),
+            checksums: show_checksums,
+            metadata_columns,
+            embed,
+            font_url: config.font_url.as_deref(),
+            i18n: &i18n,
+            highlights: highlights.as_ref().map(|highlights| Highlights {
+                recent: &highlights.recent,
+                popular: &highlights.popular,
+                dates: &dates,
+                i18n: &i18n,
+            }),
+            playlist,
+            filter: query.as_deref(),
+            rows: markup::raw(ROWS_PLACEHOLDER),
+            footer: markup::raw(FOOTER_PLACEHOLDER),
+        }
+        .to_string();
+        let (head, rest) = page
+            .split_once(ROWS_PLACEHOLDER)
+            .expect("must contain the rows placeholder");
+        let (middle, tail) = rest
+            .split_once(FOOTER_PLACEHOLDER)
+            .expect("must contain the footer placeholder");
+        let (head, middle, tail) = (head.to_owned(), middle.to_owned(), tail.to_owned());
+        let first_rows = EntryRows {
+            key_prefix,
+            path_prefix: path,
+            entries: &entries,
+            file_size_format_options,
+            dates: &dates,
+            i18n: &i18n,
+            downloads: downloads.as_ref(),
+            sizes: sizes.as_ref(),
+            checksums: show_checksums,
+            metadata_columns,
+            objects: &objects,
+        }
+        .to_string();
+        let key_prefix = key_prefix.to_owned();
+        let path_prefix = path.to_owned();
+        let mount = mount.clone();
+        let downloads = Rc::new(downloads);
+        let metadata_columns = Rc::new(metadata_columns.to_vec());
+        let totals = Rc::new(std::cell::Cell::new(totals));
+        let footer = {
+            let totals = totals.clone();
+            let i18n = i18n.clone();
+            futures_util::stream::once(async move {
+                worker::Result::Ok(
+                    Summary {
+                        totals: &totals.get(),
+                        prefix: prefix_usage,
+                        columns,
+                        file_size_format_options,
+                        i18n: &i18n,
+                    }
+                    .to_string()
+                    .into_bytes(),
+                )
+            })
+        };
+
+        let pages = futures_util::stream::try_unfold(Some(cursor), move |cursor| {
+            let key_prefix = key_prefix.clone();
+            let path_prefix = path_prefix.clone();
+            let store = store.clone();
+            let mount = mount.clone();
+            let filter = filter.clone();
+            let downloads = downloads.clone();
+            let disk_usage = disk_usage.clone();
+            let totals = totals.clone();
+            let metadata_columns = metadata_columns.clone();
+            let dates = dates.clone();
+            let i18n = i18n.clone();
+            async move {
+                let Some(cursor) = cursor else {
+                    return Ok(None);
+                };
+                let list_response =
+                    list(store.as_ref(), &key_prefix, Some(cursor), include_metadata).await?;
+                let mut entries = list_entries(&list_response, &key_prefix, &filter);
+                sorting.sort(&mut entries);
+                let mut page_totals = totals.get();
+                page_totals.add(&entries);
+                totals.set(page_totals);
+                let sizes = match &disk_usage {
+                    Some(disk_usage) => Some(
+                        disk_usage
+                            .directories(None, &mount, &path_prefix, &entries)
+                            .await?,
+                    ),
+                    None => None,
+                };
+                let objects = objects_by_name(&list_response.objects, &key_prefix);
+                let rows = EntryRows {
+                    key_prefix: &key_prefix,
+                    path_prefix: &path_prefix,
+                    entries: &entries,
+                    file_size_format_options,
+                    dates: &dates,
+                    i18n: &i18n,
+                    downloads: downloads.as_ref().as_ref(),
+                    sizes: sizes.as_ref(),
+                    checksums: show_checksums,
+                    metadata_columns: &metadata_columns,
+                    objects: &objects,
+                }
+                .to_string();
+                worker::Result::Ok(Some((rows.into_bytes(), list_response.cursor)))
+            }
+        });
+        worker::Response::from_stream(
+            futures_util::stream::iter([Ok(head.into_bytes()), Ok(first_rows.into_bytes())])
+                .chain(pages)
+                .chain(futures_util::stream::iter([Ok(middle.into_bytes())]))
+                .chain(footer)
+                .chain(futures_util::stream::iter([Ok(tail.into_bytes())])),
+        )?
+        .with_headers(headers)
+    } else if entries.len() > ROWS_PER_CHUNK {
+        sorting.sort(&mut entries);
+        headers.set("etag", &etag(&entries))?;
+        let page = EntryList {
+            readable_key_prefix,
+            downloads: downloads.is_some(),
+            checksums: show_checksums,
+            metadata_columns,
+            embed,
+            font_url: config.font_url.as_deref(),
+            i18n: &i18n,
+            highlights: highlights.as_ref().map(|highlights| Highlights {
+                recent: &highlights.recent,
+                popular: &highlights.popular,
+                dates: &dates,
+                i18n: &i18n,
+            }),
+            playlist,
+            filter: query.as_deref(),
+            rows: markup::raw(ROWS_PLACEHOLDER),
+            footer: markup::raw(
+                Summary {
+                    totals: &totals,
+                    prefix: prefix_usage,
+                    columns,
+                    file_size_format_options,
+                    i18n: &i18n,
+                }
+                .to_string(),
+            ),
+        }
+        .to_string();
+        let (head, tail) = page
+            .split_once(ROWS_PLACEHOLDER)
+            .expect("must contain the rows placeholder");
+        let (head, tail) = (head.to_owned(), tail.to_owned());
+        let key_prefix = key_prefix.to_owned();
+        let path_prefix = path.to_owned();
+        let metadata_columns = metadata_columns.to_vec();
+        let i18n = i18n.clone();
+        let chunks = 0..(entries.len() + ROWS_PER_CHUNK - 1) / ROWS_PER_CHUNK;
+        let rows = futures_util::stream::iter(chunks).map(move |index| {
+            let end = entries.len().min((index + 1) * ROWS_PER_CHUNK);
+            worker::Result::Ok(
+                EntryRows {
+                    key_prefix: &key_prefix,
+                    path_prefix: &path_prefix,
+                    entries: &entries[index * ROWS_PER_CHUNK..end],
+                    file_size_format_options,
+                    dates: &dates,
+                    i18n: &i18n,
+                    downloads: downloads.as_ref(),
+                    sizes: sizes.as_ref(),
+                    checksums: show_checksums,
+                    metadata_columns: &metadata_columns,
+                    objects: &objects,
+                }
+                .to_string()
+                .into_bytes(),
+            )
+        });
+        worker::Response::from_stream(
+            futures_util::stream::iter([Ok(head.into_bytes())])
+                .chain(rows)
+                .chain(futures_util::stream::iter([Ok(tail.into_bytes())])),
+        )?
+        .with_headers(headers)
+    } else {
+        sorting.sort(&mut entries);
+        headers.set("etag", &etag(&entries))?;
+        worker::Response::ok(
+            EntryList {
+                readable_key_prefix,
+                downloads: downloads.is_some(),
+                checksums: show_checksums,
+                metadata_columns,
+                embed,
+                font_url: config.font_url.as_deref(),
+                i18n: &i18n,
+                highlights: highlights.as_ref().map(|highlights| Highlights {
+                    recent: &highlights.recent,
+                    popular: &highlights.popular,
+                    dates: &dates,
+                    i18n: &i18n,
+                }),
+                playlist,
+                filter: query.as_deref(),
+                rows: markup::raw(format!(
+                    "{}{}",
+                    EntryRows {
+                        key_prefix,
+                        path_prefix: path,
+                        entries: &entries,
+                        file_size_format_options,
+                        dates: &dates,
+                        i18n: &i18n,
+                        downloads: downloads.as_ref(),
+                        sizes: sizes.as_ref(),
+                        checksums: show_checksums,
+                        metadata_columns,
+                        objects: &objects,
+                    },
+                    LoadMore {
+                        href: cursor.as_deref().map(|cursor| page_href(&url, cursor)),
+                        columns,
+                        i18n: &i18n,
+                    }
+                )),
+                footer: markup::raw(match cursor {
+                    Some(_) => String::new(),
+                    None => Summary {
+                        totals: &totals,
+                        prefix: prefix_usage,
+                        columns,
+                        file_size_format_options,
+                        i18n: &i18n,
+                    }
+                    .to_string(),
+                }),
+            }
+            .to_string(),
+        )?
+        .with_headers(headers)
+    };
+    if let Some(listing_cache) = listing_cache {
+        listing_cache.put(context, &mut response)?;
+        response.headers_mut().set("x-cache", "MISS")?;
+    }
+    crate::etag::conditional(request, response)
+}
+
+fn page_href(url: &url::Url, cursor: &str) -> String {
+    let pairs = url
+        .query_pairs()
+        .filter(|(name, _)| name != "cursor" && name != "partial")
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect_vec();
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.extend_pairs(pairs).append_pair("cursor", cursor);
+    format!("?{}", query.finish())
+}
+
+pub async fn unmounted(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let readable_key_prefix = if path.is_empty() { "/" } else { path };
+    let file_size_format_options = crate::sizes::options(config, None);
+    let dates = crate::dates::Dates::from_config(config);
+    let i18n = crate::i18n::Translator::from_config(config);
+    let mut entries = crate::mounts::children(config, path)
+        .into_iter()
+        .map(|name| (EntryType::Directory, format!("{path}{name}")))
+        .collect_vec();
+    crate::sorting::Sorting::from_request(config, None).sort(&mut entries);
+    if entries.is_empty() || !readable_key_prefix.ends_with('/') {
+        return crate::errors::response(environment, config, None, 404, "Not Found").await;
+    }
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/html")?;
+    crate::robots::tag(&config.robots_tag_listings, &mut headers)?;
+    crate::i18n::write_headers(config, &mut headers)?;
+    crate::security::page(config, &mut headers)?;
+    Ok(worker::Response::ok(
+        EntryList {
+            readable_key_prefix,
+            downloads: false,
+            checksums: false,
+            metadata_columns: &[],
+            embed: false,
+            font_url: config.font_url.as_deref(),
+            i18n: &i18n,
+            highlights: None,
+            playlist: None,
+            filter: None,
+            rows: EntryRows {
+                key_prefix: path,
+                path_prefix: path,
+                entries: &entries,
+                file_size_format_options,
+                dates: &dates,
+                i18n: &i18n,
+                downloads: None,
+                sizes: None,
+                checksums: false,
+                metadata_columns: &[],
+                objects: &Objects::new(),
+            },
+            footer: Summary {
+                totals: &Totals {
+                    directories: entries.len() as u64,
+                    ..Default::default()
+                },
+                prefix: None,
+                columns: 3,
+                file_size_format_options,
+                i18n: &i18n,
+            },
+        }
+        .to_string(),
+    )?
+    .with_headers(headers))
+}
+
+fn entries_json(
+    entries: &[(EntryType, String)],
+    key_prefix: &str,
+    downloads: Option<&HashMap<String, u64>>,
+    sizes: Option<&HashMap<String, crate::du::Usage>>,
+    objects: &Objects,
+) -> Vec<serde_json::Value> {
+    entries
+        .iter()
+        .map(|(entry_type, key)| {
+            let name = key.strip_prefix(key_prefix).expect("must be a prefix");
+            match entry_type {
+                EntryType::File { size, uploaded } => {
+                    let mut entry = serde_json::json!({
+                        "name": name,
+                        "type": "file",
+                        "size": size,
+                        "uploaded": uploaded.to_rfc3339(),
+                        "checksums": objects
+                            .get(name)
+                            .into_iter()
+                            .flat_map(|object| object.checksums.iter().cloned())
+                            .collect::<HashMap<_, _>>(),
+                    });
+                    if let Some(downloads) = downloads {
+                        entry["downloads"] =
+                            downloads.get(name).copied().unwrap_or_default().into();
+                    }
+                    entry
+                }
+                EntryType::Directory => {
+                    let mut entry = serde_json::json!({ "name": name, "type": "directory" });
+                    if let Some(usage) = sizes.and_then(|sizes| sizes.get(name)) {
+                        entry["size"] = usage.size.into();
+                        entry["count"] = usage.count.into();
+                    }
+                    entry
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn objects_by_name<'a>(
+    objects: impl IntoIterator<Item = &'a crate::store::Object>,
+    key_prefix: &str,
+) -> Objects {
+    objects
+        .into_iter()
+        .filter_map(|object| {
+            let name = object.key.strip_prefix(key_prefix)?;
+            Some((name.to_owned(), object.clone()))
+        })
+        .collect()
+}
+
+pub async fn list(
+    store: &dyn crate::store::ObjectStore,
+    key_prefix: &str,
+    cursor: Option<String>,
+    include_metadata: bool,
+) -> worker::Result<crate::store::Listing> {
+    store
+        .list(crate::store::ListOptions {
+            prefix: key_prefix.to_owned(),
+            delimiter: Some("/".into()),
+            cursor,
+            include_metadata,
+            ..Default::default()
+        })
+        .await
+}
+
+pub fn list_entries(
+    list_response: &crate::store::Listing,
+    key_prefix: &str,
+    filter: &crate::filter::EntryFilter,
+) -> Vec<(EntryType, String)> {
+    let mut entries: Vec<(EntryType, String)> = list_response
+        .delimited_prefixes
+        .iter()
+        .map(|key| (EntryType::Directory, key.clone()))
+        .chain(list_response.objects.iter().map(|object| {
+            (
+                EntryType::File {
+                    size: object.size,
+                    uploaded: object.uploaded,
+                },
+                object.key.clone(),
+            )
+        }))
+        .collect();
+    filter.apply(key_prefix, &mut entries);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    fn store() -> crate::store::MemoryStore {
+        crate::store::MemoryStore::with_files(&[
+            ("docs/.hidden", ""),
+            ("docs/_headers", ""),
+            ("docs/a.txt", "a"),
+            ("docs/a.txt.gz", "a"),
+            ("docs/b.txt", "bb"),
+            ("docs/nested/c.txt", "ccc"),
+            ("other.txt", ""),
+        ])
+    }
+
+    fn names(entries: &[(EntryType, String)], key_prefix: &str) -> Vec<String> {
+        entries
+            .iter()
+            .map(|(_, key)| key.strip_prefix(key_prefix).unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn lists_one_directory_level() {
+        let config = crate::config::Config::from_vars(&[("PRECOMPRESSED", "true")]);
+        let store = store();
+        let listing = list(&store, "docs/", None, false)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let filter = crate::filter::EntryFilter::from_config(&config, false);
+        let entries = list_entries(&listing, "docs/", &filter);
+        assert_eq!(names(&entries, "docs/"), ["nested/", "a.txt", "b.txt"]);
+        assert!(matches!(entries[0].0, EntryType::Directory));
+        assert!(matches!(entries[2].0, EntryType::File { size: 2, .. }));
+
+        let filter = crate::filter::EntryFilter::from_config(&config, true);
+        let entries = list_entries(&listing, "docs/", &filter);
+        assert_eq!(
+            names(&entries, "docs/"),
+            ["nested/", ".hidden", "_headers", "a.txt", "a.txt.gz", "b.txt"]
+        );
+    }
+
+    #[test]
+    fn filters_by_query() {
+        let store = store();
+        let listing = list(&store, "docs/", None, false)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let filter = crate::filter::EntryFilter::from_config(&Default::default(), false)
+            .matching(Some(" NEST "));
+        let entries = list_entries(&listing, "docs/", &filter);
+        assert_eq!(names(&entries, "docs/"), ["nested/"]);
+    }
+
+    #[test]
+    fn keys_objects_by_name() {
+        let store = store();
+        let listing = list(&store, "docs/", None, true)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let objects = objects_by_name(&listing.objects, "docs/");
+        assert_eq!(objects["b.txt"].size, 2);
+        assert!(!objects.contains_key("nested/c.txt"));
+    }
+
+    fn rendered(config: &crate::config::Config, store: &crate::store::MemoryStore) -> String {
+        let listing = list(store, "docs/", None, true)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let filter = crate::filter::EntryFilter::from_config(config, false);
+        let entries = list_entries(&listing, "docs/", &filter);
+        let objects = objects_by_name(&listing.objects, "docs/");
+        let downloads = HashMap::from([("b.txt".to_owned(), 7)]);
+        let sizes = HashMap::from([("nested/".to_owned(), crate::du::Usage { size: 3, count: 1 })]);
+        let options = crate::sizes::options(config, None);
+        let dates = crate::dates::Dates::from_config(config);
+        let i18n = crate::i18n::Translator::from_config(config);
+        EntryList {
+            readable_key_prefix: "docs/",
+            downloads: true,
+            checksums: false,
+            metadata_columns: &[],
+            embed: false,
+            font_url: None,
+            i18n: &i18n,
+            highlights: None,
+            playlist: None,
+            filter: None,
+            rows: EntryRows {
+                key_prefix: "docs/",
+                path_prefix: "docs/",
+                entries: &entries,
+                file_size_format_options: options,
+                dates: &dates,
+                i18n: &i18n,
+                downloads: Some(&downloads),
+                sizes: Some(&sizes),
+                checksums: false,
+                metadata_columns: &[],
+                objects: &objects,
+            },
+            footer: Summary {
+                totals: &Totals {
+                    files: 2,
+                    directories: 1,
+                    size: 3,
+                },
+                prefix: None,
+                columns: 4,
+                file_size_format_options: options,
+                i18n: &i18n,
+            },
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn renders_rows() {
+        let html = rendered(&Default::default(), &store());
+        assert!(html.contains(
+            r#"<tr data-name="a.txt"><td>📄 <a href="/docs/a.txt">a.txt</a></td><td>1 B</td>"#
+        ));
+        assert!(html.contains(r#"<td>7</td>"#));
+        assert!(html.contains(r#"<td title="1 file">3 B</td>"#));
+        assert!(html.contains("2 files, 1 directory, 3 B"));
+        assert!(html.contains(r#"<h1>docs/</h1>"#));
+        assert!(!html.contains(r#"data-name=".hidden""#));
+        assert!(!html.contains(r#"data-name="_headers""#));
+    }
+
+    #[test]
+    fn translates_rows() {
+        let config = crate::config::Config::from_vars(&[("LOCALE", "fr")]);
+        let html = rendered(&config, &store());
+        assert!(html.contains("2 fichiers"));
+    }
+
+    #[test]
+    fn describes_entries_as_json() {
+        let store = store();
+        let listing = list(&store, "docs/", None, true)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let filter = crate::filter::EntryFilter::from_config(&Default::default(), false);
+        let entries = list_entries(&listing, "docs/", &filter);
+        let objects = objects_by_name(&listing.objects, "docs/");
+        let downloads = HashMap::from([("b.txt".to_owned(), 7)]);
+        let json = entries_json(&entries, "docs/", Some(&downloads), None, &objects);
+        assert_eq!(
+            json[0],
+            serde_json::json!({ "name": "nested/", "type": "directory" })
+        );
+        assert_eq!(json[3]["name"], "b.txt");
+        assert_eq!(json[3]["size"], 2);
+        assert_eq!(json[3]["downloads"], 7);
+        assert_eq!(json[1]["downloads"], 0);
+        assert!(json[3]["checksums"]["sha256"].is_string());
+    }
+}
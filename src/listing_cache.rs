@@ -0,0 +1,157 @@
+use sha2::Digest;
+
+const PURGE_BATCH_SIZE: usize = 30;
+
+pub struct ListingCache {
+    key: String,
+    tag: String,
+    ttl: u64,
+    bypass: bool,
+}
+
+impl ListingCache {
+    pub fn from_config(
+        request: &worker::Request,
+        config: &crate::config::Config,
+        key_prefix: &str,
+    ) -> worker::Result<Option<Self>> {
+        let Some(ttl) = config.listing_cache_ttl else {
+            return Ok(None);
+        };
+        let bypass = request
+            .headers()
+            .get("cache-control")?
+            .is_some_and(|cache_control| cache_control.contains("no-cache"));
+        let mut key = request.url()?;
+        if config.locale.is_none() && config.language != crate::i18n::DEFAULT_LANGUAGE {
+            key.query_pairs_mut().append_pair("lang", &config.language);
+        }
+        Ok(Some(Self {
+            key: key.to_string(),
+            tag: tag(key_prefix),
+            ttl,
+            bypass,
+        }))
+    }
+
+    pub async fn get(&self) -> worker::Result<Option<worker::Response>> {
+        if self.bypass {
+            return Ok(None);
+        }
+        worker::Cache::default().get(self.key.as_str(), false).await
+    }
+
+    pub fn put(
+        self,
+        context: &worker::Context,
+        response: &mut worker::Response,
+    ) -> worker::Result<()> {
+        let mut cached = response.cloned()?;
+        cached
+            .headers_mut()
+            .set("cache-control", &format!("s-maxage={}", self.ttl))?;
+        cached.headers_mut().set("cache-tag", &self.tag)?;
+        context.wait_until(async move {
+            if let Err(err) = worker::Cache::default().put(self.key, cached).await {
+                worker::console_error!("failed to cache the listing: {err}");
+            }
+        });
+        Ok(())
+    }
+}
+
+pub async fn purge(config: &crate::config::Config, keys: &[String]) -> worker::Result<()> {
+    let prefixes = crate::prefixes(keys);
+
+    match (&config.cloudflare_zone_id, &config.cloudflare_api_token) {
+        (Some(zone_id), Some(api_token)) => {
+            let tags: Vec<String> = prefixes.into_iter().map(tag).collect();
+            for tags in tags.chunks(PURGE_BATCH_SIZE) {
+                purge_tags(zone_id, api_token, tags).await?;
+            }
+        }
+        _ => {
+            let cache = worker::Cache::default();
+            for url in urls(config, &prefixes) {
+                cache.delete(url, false).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn urls(config: &crate::config::Config, prefixes: &[&str]) -> Vec<String> {
+    let mut urls = Vec::new();
+    for origin in &config.listing_cache_origins {
+        let origin = origin.trim_end_matches('/');
+        let host = url::Url::parse(origin)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+            .unwrap_or_default();
+        for prefix in prefixes {
+            for path in crate::mounts::public_paths(config, &host, prefix) {
+                urls.push(format!("{origin}{}", crate::href(&path)));
+            }
+        }
+    }
+    urls
+}
+
+async fn purge_tags(zone_id: &str, api_token: &str, tags: &[String]) -> worker::Result<()> {
+    let mut headers = worker::Headers::new();
+    headers.set("authorization", &format!("Bearer {api_token}"))?;
+    headers.set("content-type", "application/json")?;
+    let mut init = worker::RequestInit::new();
+    init.with_method(worker::Method::Post)
+        .with_headers(headers)
+        .with_body(Some(
+            serde_json::to_string(&serde_json::json!({ "tags": tags }))?.into(),
+        ));
+    let response = worker::Fetch::Request(worker::Request::new_with_init(
+        &format!("https://api.cloudflare.com/client/v4/zones/{zone_id}/purge_cache"),
+        &init,
+    )?)
+    .send()
+    .await?;
+    if response.status_code() != 200 {
+        return Err(worker::Error::RustError(format!(
+            "purging the cache failed with status {}",
+            response.status_code()
+        )));
+    }
+    Ok(())
+}
+
+fn tag(key_prefix: &str) -> String {
+    let digest = sha2::Sha256::digest(key_prefix.as_bytes());
+    format!("listing-{}", crate::hex(&digest[..16]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purges_public_paths_under_the_root_prefix() {
+        let config = crate::config::Config::from_vars(&[
+            ("LISTING_CACHE_ORIGINS", "https://files.example.com/"),
+            ("ROOT_PREFIX", "public"),
+            ("BUCKET_MOUNTS", r#"{"media": "BUCKET_MEDIA"}"#),
+        ]);
+        let keys = [
+            "public/my docs/a.txt".to_owned(),
+            "private/b.txt".to_owned(),
+        ];
+        assert_eq!(
+            urls(&config, &crate::prefixes(&keys)),
+            [
+                "https://files.example.com/media/",
+                "https://files.example.com/media/private/",
+                "https://files.example.com/",
+                "https://files.example.com/media/public/",
+                "https://files.example.com/media/public/my%20docs/",
+                "https://files.example.com/my%20docs/",
+            ]
+        );
+    }
+}
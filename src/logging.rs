@@ -0,0 +1,103 @@
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "off" => Ok(Level::Off),
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(format!("unknown log level `{value}`")),
+        }
+    }
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+pub fn request_id(request: &worker::Request) -> worker::Result<String> {
+    if let Some(id) = request
+        .headers()
+        .get("x-request-id")?
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+    {
+        return Ok(id);
+    }
+    if let Some(ray) = request.headers().get("cf-ray")? {
+        return Ok(ray);
+    }
+    Ok(crate::random_hex(16))
+}
+
+pub fn emit(config: &crate::config::Config, level: Level, mut fields: serde_json::Value) {
+    if level == Level::Off || level > config.log_level {
+        return;
+    }
+    if let Some(fields) = fields.as_object_mut() {
+        fields.insert("level".into(), level.name().into());
+    }
+    match level {
+        Level::Error => worker::console_error!("{fields}"),
+        Level::Warn => worker::console_warn!("{fields}"),
+        _ => worker::console_log!("{fields}"),
+    }
+}
+
+pub fn error(config: &crate::config::Config, request_id: &str, err: &worker::Error) {
+    emit(
+        config,
+        Level::Error,
+        serde_json::json!({ "request_id": request_id, "error": err.to_string() }),
+    );
+}
+
+pub fn request(
+    config: &crate::config::Config,
+    request: &worker::Request,
+    response: &worker::Response,
+    request_id: &str,
+    started: u64,
+) -> worker::Result<()> {
+    let status = response.status_code();
+    let level = match status {
+        500.. => Level::Error,
+        400.. => Level::Warn,
+        _ => Level::Info,
+    };
+    if level > config.log_level {
+        return Ok(());
+    }
+    emit(
+        config,
+        level,
+        serde_json::json!({
+            "request_id": request_id,
+            "ray": request.headers().get("cf-ray")?,
+            "method": request.method().to_string(),
+            "path": request.path(),
+            "status": status,
+            "latency_ms": worker::Date::now().as_millis().saturating_sub(started),
+            "identity": crate::auth::identity(request, config)?,
+        }),
+    );
+    Ok(())
+}
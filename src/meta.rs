@@ -0,0 +1,26 @@
+use std::collections::BTreeMap;
+
+pub async fn handle(
+    request: &worker::Request,
+    store: &dyn crate::store::ObjectStore,
+    path: &str,
+    key: &str,
+) -> worker::Result<Option<worker::Response>> {
+    if !request.url()?.query_pairs().any(|(name, _)| name == "meta") {
+        return Ok(None);
+    }
+    let Some(object) = store.head(key).await? else {
+        return Ok(None);
+    };
+    let map = |pairs: &[(String, String)]| pairs.iter().cloned().collect::<BTreeMap<_, _>>();
+    worker::Response::from_json(&serde_json::json!({
+        "path": path,
+        "size": object.size,
+        "uploaded": object.uploaded.to_rfc3339(),
+        "etag": object.etag,
+        "checksums": map(&object.checksums),
+        "httpMetadata": map(&object.http_metadata),
+        "customMetadata": map(&object.custom_metadata),
+    }))
+    .map(Some)
+}
@@ -0,0 +1,66 @@
+pub fn allowed(request: &worker::Request, config: &crate::config::Config) -> String {
+    methods(config, &request.path()).join(", ")
+}
+
+fn methods(config: &crate::config::Config, path: &str) -> Vec<&'static str> {
+    let file = !path.ends_with('/');
+    let mut methods = vec!["GET", "HEAD", "OPTIONS"];
+    if (file && crate::turnstile::enabled(config))
+        || (path == crate::graphql::PATH && config.graphql)
+        || (path == crate::trash::PATH && config.trash_prefix.is_some())
+    {
+        methods.push("POST");
+    }
+    if config.allow_delete && file {
+        methods.push("DELETE");
+    }
+    methods
+}
+
+pub fn options(
+    request: &worker::Request,
+    config: &crate::config::Config,
+) -> worker::Result<worker::Response> {
+    let mut headers = worker::Headers::new();
+    headers.set("allow", &allowed(request, config))?;
+    Ok(worker::Response::empty()?
+        .with_status(204)
+        .with_headers(headers))
+}
+
+pub async fn not_allowed(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<worker::Response> {
+    let mut response =
+        crate::errors::for_request(request, environment, config, 405, "Method Not Allowed").await?;
+    response
+        .headers_mut()
+        .set("allow", &allowed(request, config))?;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_each_method_once() {
+        let config = crate::config::Config::from_vars(&[
+            ("ALLOW_DELETE", "true"),
+            ("TRASH_PREFIX", ".trash"),
+            ("TURNSTILE_SITE_KEY", "site"),
+            ("TURNSTILE_SECRET_KEY", "secret"),
+        ]);
+        assert_eq!(
+            methods(&config, crate::trash::PATH),
+            ["GET", "HEAD", "OPTIONS", "POST", "DELETE"]
+        );
+        assert_eq!(methods(&config, "/docs/"), ["GET", "HEAD", "OPTIONS"]);
+        assert_eq!(
+            methods(&Default::default(), crate::trash::PATH),
+            ["GET", "HEAD", "OPTIONS"]
+        );
+    }
+}
@@ -0,0 +1,12 @@
+pub fn apply(
+    config: &crate::config::Config,
+    key: &str,
+    headers: &mut worker::Headers,
+) -> worker::Result<()> {
+    if let Some(content_type) =
+        crate::extension(key).and_then(|extension| config.mime_types.get(&extension))
+    {
+        headers.set("content-type", content_type)?;
+    }
+    Ok(())
+}
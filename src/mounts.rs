@@ -0,0 +1,333 @@
+use itertools::Itertools;
+
+const DEFAULT_BINDING: &str = "BUCKET";
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+pub enum Host {
+    Binding(String),
+    Mount {
+        binding: Option<String>,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+#[derive(Clone)]
+pub struct Mount {
+    pub store: std::rc::Rc<dyn crate::store::ObjectStore>,
+    pub binding: String,
+    pub path: String,
+    pub key_prefix: String,
+}
+
+impl Mount {
+    pub fn serves(&self, config: &crate::config::Config, path: &str) -> bool {
+        locate(config, "", path).path == self.path
+    }
+
+    pub fn key(&self, path: &str) -> String {
+        format!(
+            "{}{}",
+            self.key_prefix,
+            path.strip_prefix(self.path.as_str()).unwrap_or(path)
+        )
+    }
+}
+
+pub fn resolve(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    host: &str,
+    path: &str,
+) -> worker::Result<Option<Mount>> {
+    let location = locate(config, host, path);
+    let store = match location.mounted {
+        true => crate::store::binding(environment, &location.binding)?,
+        false => match crate::store::binding(environment, &location.binding) {
+            Ok(store) => store,
+            Err(_) => return Ok(None),
+        },
+    };
+    Ok(Some(Mount {
+        store,
+        binding: location.binding,
+        path: location.path,
+        key_prefix: location.key_prefix,
+    }))
+}
+
+struct Location {
+    binding: String,
+    path: String,
+    key_prefix: String,
+    mounted: bool,
+}
+
+fn locate(config: &crate::config::Config, host: &str, path: &str) -> Location {
+    if let Some((mount, binding)) = mounts(config)
+        .filter(|(mount, _)| path.starts_with(&format!("{mount}/")))
+        .max_by_key(|(mount, _)| mount.len())
+    {
+        return Location {
+            binding: binding.to_owned(),
+            path: format!("{mount}/"),
+            key_prefix: String::new(),
+            mounted: true,
+        };
+    }
+
+    let (binding, prefix) = match config.bucket_hosts.get(host) {
+        Some(Host::Binding(binding)) => (binding.as_str(), ""),
+        Some(Host::Mount { binding, prefix }) => (
+            binding.as_deref().unwrap_or(DEFAULT_BINDING),
+            prefix.as_str(),
+        ),
+        None => (DEFAULT_BINDING, config.root_prefix.as_str()),
+    };
+    let prefix = prefix.trim_matches('/');
+    Location {
+        binding: binding.to_owned(),
+        path: String::new(),
+        key_prefix: if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{prefix}/")
+        },
+        mounted: false,
+    }
+}
+
+pub fn for_request(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<Option<Mount>> {
+    let url = request.url()?;
+    let path =
+        urlencoding::decode(url.path()).map_err(|err| worker::Error::RustError(err.to_string()))?;
+    resolve(
+        environment,
+        config,
+        url.host_str().unwrap_or_default(),
+        path.trim_start_matches('/'),
+    )
+}
+
+pub fn is_mount(config: &crate::config::Config, path: &str) -> bool {
+    mounts(config).any(|(mount, _)| mount == path)
+}
+
+pub fn children(config: &crate::config::Config, path: &str) -> Vec<String> {
+    mounts(config)
+        .filter_map(|(mount, _)| mount.strip_prefix(path))
+        .filter(|name| !name.contains('/'))
+        .map(|name| format!("{name}/"))
+        .collect()
+}
+
+pub fn paths(config: &crate::config::Config) -> Vec<String> {
+    mounts(config)
+        .map(|(mount, _)| format!("{mount}/"))
+        .collect()
+}
+
+pub fn bindings(config: &crate::config::Config) -> std::collections::BTreeSet<&str> {
+    let hosts = config.bucket_hosts.values().map(|host| match host {
+        Host::Binding(binding) => binding.as_str(),
+        Host::Mount { binding, .. } => binding.as_deref().unwrap_or(DEFAULT_BINDING),
+    });
+    mounts(config)
+        .map(|(_, binding)| binding)
+        .chain(hosts)
+        .chain([DEFAULT_BINDING])
+        .collect()
+}
+
+pub fn public_paths(config: &crate::config::Config, host: &str, key: &str) -> Vec<String> {
+    let mounted = mounts(config).map(|(mount, binding)| Location {
+        binding: binding.to_owned(),
+        path: format!("{mount}/"),
+        key_prefix: String::new(),
+        mounted: true,
+    });
+    std::iter::once(locate(config, host, ""))
+        .chain(mounted)
+        .filter_map(|location| {
+            let path = format!(
+                "{}{}",
+                location.path,
+                key.strip_prefix(&location.key_prefix)?
+            );
+            let served = locate(config, host, &path);
+            (served.binding == location.binding && served.path == location.path).then_some(path)
+        })
+        .sorted()
+        .collect()
+}
+
+fn mounts(config: &crate::config::Config) -> impl Iterator<Item = (&str, &str)> {
+    config
+        .bucket_mounts
+        .iter()
+        .map(|(mount, binding)| (mount.trim_matches('/'), binding.as_str()))
+        .filter(|(mount, _)| !mount.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    fn config() -> crate::config::Config {
+        crate::config::Config::from_vars(&[
+            (
+                "BUCKET_MOUNTS",
+                r#"{"media": "BUCKET_MEDIA", "media/video/": "BUCKET_VIDEO"}"#,
+            ),
+            (
+                "BUCKET_HOSTS",
+                r#"{"a.example": "BUCKET_A", "b.example": {"prefix": "/b/"}, "c.example": {"binding": "BUCKET_C"}}"#,
+            ),
+            ("ROOT_PREFIX", "public"),
+        ])
+    }
+
+    fn mount(location: Location, store: crate::store::MemoryStore) -> Mount {
+        Mount {
+            store: std::rc::Rc::new(store),
+            binding: location.binding,
+            path: location.path,
+            key_prefix: location.key_prefix,
+        }
+    }
+
+    #[test]
+    fn picks_the_longest_path_mount() {
+        let config = config();
+        let location = locate(&config, "a.example", "media/video/clip.mp4");
+        assert_eq!(location.binding, "BUCKET_VIDEO");
+        assert_eq!(location.path, "media/video/");
+        assert!(location.mounted);
+        let location = locate(&config, "a.example", "media/song.mp3");
+        assert_eq!(location.binding, "BUCKET_MEDIA");
+        assert_eq!(location.path, "media/");
+        assert_eq!(location.key_prefix, "");
+        assert_eq!(locate(&config, "", "mediatheque/x").binding, "BUCKET");
+    }
+
+    #[test]
+    fn routes_hosts() {
+        let config = config();
+        let location = locate(&config, "a.example", "file");
+        assert_eq!(
+            (location.binding.as_str(), location.key_prefix.as_str()),
+            ("BUCKET_A", "")
+        );
+        let location = locate(&config, "b.example", "file");
+        assert_eq!(
+            (location.binding.as_str(), location.key_prefix.as_str()),
+            ("BUCKET", "b/")
+        );
+        let location = locate(&config, "c.example", "file");
+        assert_eq!(
+            (location.binding.as_str(), location.key_prefix.as_str()),
+            ("BUCKET_C", "")
+        );
+        let location = locate(&config, "other.example", "file");
+        assert_eq!(
+            (location.binding.as_str(), location.key_prefix.as_str()),
+            ("BUCKET", "public/")
+        );
+        assert!(!location.mounted);
+    }
+
+    #[test]
+    fn maps_paths_to_keys() {
+        let config = config();
+        let store = crate::store::MemoryStore::new();
+        let root = mount(locate(&config, "other.example", "docs/a.txt"), store);
+        assert_eq!(root.key("docs/a.txt"), "public/docs/a.txt");
+        let store = crate::store::MemoryStore::new();
+        let media = mount(locate(&config, "", "media/a/b.mp3"), store);
+        assert_eq!(media.key("media/a/b.mp3"), "a/b.mp3");
+        assert_eq!(media.key("media/"), "");
+    }
+
+    #[test]
+    fn serves_only_the_prefix_of_a_mount() {
+        let config = config();
+        let store = crate::store::MemoryStore::with_files(&[
+            ("public/index.html", "index"),
+            ("public/docs/a.txt", "a"),
+            ("private/secret.txt", "secret"),
+        ]);
+        let root = mount(locate(&config, "other.example", ""), store);
+        let listing = crate::listing::list(root.store.as_ref(), &root.key(""), None, false)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        let filter = crate::filter::EntryFilter::from_config(&config, false);
+        let entries = crate::listing::list_entries(&listing, &root.key_prefix, &filter);
+        let keys: Vec<&str> = entries.iter().map(|(_, key)| key.as_str()).collect();
+        assert_eq!(keys, ["public/docs/", "public/index.html"]);
+        let secret = root.key("../private/secret.txt");
+        assert!(root
+            .store
+            .head(&secret)
+            .now_or_never()
+            .unwrap()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn serves_paths_not_shadowed_by_a_deeper_mount() {
+        let config = config();
+        let store = crate::store::MemoryStore::new();
+        let root = mount(locate(&config, "", ""), store);
+        let store = crate::store::MemoryStore::new();
+        let media = mount(locate(&config, "", "media/"), store);
+        assert!(root.serves(&config, "docs/a.txt"));
+        assert!(!root.serves(&config, "media/a.mp3"));
+        assert!(media.serves(&config, "media/a.mp3"));
+        assert!(!media.serves(&config, "media/video/a.mp4"));
+        assert_eq!(paths(&config).len(), 2);
+    }
+
+    #[test]
+    fn lists_mounts_as_children() {
+        let config = config();
+        assert_eq!(children(&config, ""), ["media/"]);
+        assert_eq!(children(&config, "media/"), ["video/"]);
+        assert!(is_mount(&config, "media"));
+        assert!(!is_mount(&config, "media/"));
+        let bindings: Vec<&str> = bindings(&config).into_iter().collect();
+        assert_eq!(
+            bindings,
+            [
+                "BUCKET",
+                "BUCKET_A",
+                "BUCKET_C",
+                "BUCKET_MEDIA",
+                "BUCKET_VIDEO"
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_keys_back_to_paths() {
+        let config = config();
+        let paths = public_paths(&config, "", "public/docs/");
+        assert_eq!(
+            paths,
+            ["docs/", "media/public/docs/", "media/video/public/docs/"]
+        );
+        assert_eq!(public_paths(&config, "", "")[0], "media/");
+        assert_eq!(public_paths(&config, "b.example", "b/")[0], "");
+        assert!(!public_paths(&config, "", "private/").contains(&"private/".to_owned()));
+        let video = public_paths(&config, "", "video/");
+        assert_eq!(video, ["media/video/video/"]);
+    }
+}
@@ -0,0 +1,20 @@
+pub async fn check(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<Option<worker::Response>> {
+    if !config.mtls_required {
+        return Ok(None);
+    }
+    let verified = request
+        .cf()
+        .tls_client_auth()
+        .is_some_and(|auth| auth.cert_presented() == "1" && auth.cert_verified() == "SUCCESS");
+    if verified {
+        Ok(None)
+    } else {
+        crate::errors::for_request(request, environment, config, 403, "Forbidden")
+            .await
+            .map(Some)
+    }
+}
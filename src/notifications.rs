@@ -0,0 +1,44 @@
+#[derive(serde::Deserialize)]
+pub struct Notification {
+    action: String,
+    object: NotificationObject,
+}
+
+#[derive(serde::Deserialize)]
+struct NotificationObject {
+    key: String,
+}
+
+pub async fn consume(
+    message_batch: worker::MessageBatch<Notification>,
+    environment: &worker::Env,
+) -> worker::Result<()> {
+    let keys: Vec<String> = message_batch
+        .iter()
+        .filter_map(|message| match message {
+            Ok(message) => Some(message.body),
+            Err(err) => {
+                worker::console_warn!("skipping an unreadable notification: {err}");
+                None
+            }
+        })
+        .filter(|notification| {
+            matches!(
+                notification.action.as_str(),
+                "PutObject"
+                    | "CopyObject"
+                    | "CompleteMultipartUpload"
+                    | "DeleteObject"
+                    | "LifecycleDeletion"
+            )
+        })
+        .map(|notification| notification.object.key)
+        .collect();
+
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let config = crate::config::Config::load(environment).await?;
+    crate::du::invalidate(environment, &config, &keys).await?;
+    crate::listing_cache::purge(&config, &keys).await
+}
@@ -0,0 +1,197 @@
+pub const PATH: &str = "/openapi.json";
+
+pub fn handle(request: &worker::Request) -> worker::Result<worker::Response> {
+    let origin = request.url()?.origin().ascii_serialization();
+    let flag = |name: &str, description: &str| {
+        serde_json::json!({
+            "name": name,
+            "in": "query",
+            "description": description,
+            "allowEmptyValue": true,
+            "schema": { "type": "string" },
+        })
+    };
+    worker::Response::from_json(&serde_json::json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": origin }],
+        "paths": {
+            "/{directory}/": {
+                "get": {
+                    "summary": "List a directory, or report its size or checksums",
+                    "parameters": [
+                        {
+                            "name": "directory",
+                            "in": "path",
+                            "required": true,
+                            "description": "Directory path, which may contain slashes.",
+                            "schema": { "type": "string" },
+                        },
+                        {
+                            "name": "format",
+                            "in": "query",
+                            "description": "Return the listing as JSON.",
+                            "schema": { "type": "string", "enum": ["json"] },
+                        },
+                        flag("recursive", "List every file below the directory instead of only its children."),
+                        flag("du", "Return the total size and number of files below the directory."),
+                        flag("sha256sums", "Return a `sha256sum`-compatible manifest of the files."),
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "The listing, usage or manifest.",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "oneOf": [
+                                            { "$ref": "#/components/schemas/Listing" },
+                                            { "$ref": "#/components/schemas/Usage" },
+                                        ],
+                                    },
+                                },
+                                "text/plain": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "404": { "description": "The directory is empty or does not exist." },
+                    },
+                },
+            },
+            "/{file}": {
+                "get": {
+                    "summary": "Download a file, or return its metadata",
+                    "parameters": [
+                        {
+                            "name": "file",
+                            "in": "path",
+                            "required": true,
+                            "description": "File path, which may contain slashes.",
+                            "schema": { "type": "string" },
+                        },
+                        flag("meta", "Return the object's metadata as JSON instead of its contents."),
+                        flag("torrent", "Return a .torrent for the file that uses this URL as a web seed."),
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "The file or its metadata.",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Metadata" },
+                                },
+                                "application/x-bittorrent": {
+                                    "schema": { "type": "string", "format": "binary" },
+                                },
+                                "application/octet-stream": {
+                                    "schema": { "type": "string", "format": "binary" },
+                                },
+                            },
+                        },
+                        "206": { "description": "The requested byte ranges of the file." },
+                        "404": { "description": "The file does not exist." },
+                        "413": { "description": "The file is larger than `TORRENT_MAX_SIZE` for `?torrent`." },
+                    },
+                },
+            },
+            "/_health": {
+                "get": {
+                    "summary": "Check that the bucket bindings are reachable",
+                    "responses": {
+                        "200": {
+                            "description": "Every bucket answered.",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Health" },
+                                },
+                            },
+                        },
+                        "503": {
+                            "description": "At least one bucket failed.",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Health" },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+        "components": {
+            "schemas": {
+                "Checksums": {
+                    "type": "object",
+                    "description": "Hex-encoded checksums keyed by algorithm.",
+                    "additionalProperties": { "type": "string" },
+                },
+                "Entry": {
+                    "type": "object",
+                    "required": ["name", "type"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "type": { "type": "string", "enum": ["file", "directory"] },
+                        "size": { "type": "integer" },
+                        "count": { "type": "integer" },
+                        "uploaded": { "type": "string", "format": "date-time" },
+                        "downloads": { "type": "integer" },
+                        "checksums": { "$ref": "#/components/schemas/Checksums" },
+                    },
+                },
+                "Listing": {
+                    "type": "object",
+                    "required": ["path", "entries"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "entries": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/Entry" },
+                        },
+                    },
+                },
+                "Usage": {
+                    "type": "object",
+                    "required": ["path", "size", "count"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "size": { "type": "integer" },
+                        "count": { "type": "integer" },
+                    },
+                },
+                "Metadata": {
+                    "type": "object",
+                    "required": ["path", "size", "uploaded", "etag"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "size": { "type": "integer" },
+                        "uploaded": { "type": "string", "format": "date-time" },
+                        "etag": { "type": "string" },
+                        "checksums": { "$ref": "#/components/schemas/Checksums" },
+                        "httpMetadata": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                        },
+                        "customMetadata": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                        },
+                    },
+                },
+                "Health": {
+                    "type": "object",
+                    "required": ["status", "version", "buckets"],
+                    "properties": {
+                        "status": { "type": "string", "enum": ["ok", "error"] },
+                        "version": { "type": "string" },
+                        "commit": { "type": "string", "nullable": true },
+                        "buckets": {
+                            "type": "object",
+                            "description": "`ok` or the error, keyed by binding.",
+                            "additionalProperties": { "type": "string" },
+                        },
+                    },
+                },
+            },
+        },
+    }))
+}
@@ -0,0 +1,45 @@
+pub fn matches(pattern: &str, input: &str) -> Option<Vec<(String, String)>> {
+    if let Some(rest) = pattern.strip_prefix('*') {
+        return (0..=input.len())
+            .rev()
+            .filter(|index| input.is_char_boundary(*index))
+            .find_map(|index| {
+                let mut captures = matches(rest, &input[index..])?;
+                captures.push(("splat".to_owned(), input[..index].to_owned()));
+                Some(captures)
+            });
+    }
+    if let Some(rest) = pattern.strip_prefix(':') {
+        let name_length = rest
+            .find(|char: char| !char.is_ascii_alphanumeric() && char != '_')
+            .unwrap_or(rest.len());
+        if name_length > 0 {
+            let (name, rest) = rest.split_at(name_length);
+            let segment_length = input.find('/').unwrap_or(input.len());
+            return (1..=segment_length)
+                .rev()
+                .filter(|index| input.is_char_boundary(*index))
+                .find_map(|index| {
+                    let mut captures = matches(rest, &input[index..])?;
+                    captures.push((name.to_owned(), input[..index].to_owned()));
+                    Some(captures)
+                });
+        }
+    }
+    match (pattern.chars().next(), input.chars().next()) {
+        (None, None) => Some(Vec::new()),
+        (Some(expected), Some(actual)) if expected == actual => {
+            matches(&pattern[expected.len_utf8()..], &input[actual.len_utf8()..])
+        }
+        _ => None,
+    }
+}
+
+pub fn substitute(template: &str, mut captures: Vec<(String, String)>) -> String {
+    captures.sort_by_key(|(placeholder, _)| std::cmp::Reverse(placeholder.len()));
+    captures
+        .iter()
+        .fold(template.to_owned(), |value, (placeholder, capture)| {
+            value.replace(&format!(":{placeholder}"), capture)
+        })
+}
@@ -0,0 +1,65 @@
+const AUDIO_EXTENSIONS: [&str; 9] = [
+    "aac", "aiff", "flac", "m4a", "mp3", "oga", "ogg", "opus", "wav",
+];
+
+pub fn requested(url: &url::Url) -> bool {
+    url.query_pairs()
+        .any(|(name, value)| name == "playlist" && value == "m3u")
+}
+
+pub fn is_audio(config: &crate::config::Config, key: &str) -> bool {
+    crate::extension(key).is_some_and(|extension| match config.mime_types.get(&extension) {
+        Some(content_type) => content_type.starts_with("audio/"),
+        None => AUDIO_EXTENSIONS.contains(&extension.as_str()),
+    })
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    let url = request.url()?;
+    let origin = url.origin().ascii_serialization();
+    let recursive = url
+        .query_pairs()
+        .any(|(name, value)| name == "recursive" && value != "0");
+    let filter = crate::filter::EntryFilter::from_config(config, false);
+    let files = match recursive {
+        true => crate::walk::files(mount, path, &filter).await?,
+        false => crate::walk::children(mount, path, &filter).await?,
+    };
+    let mut playlist = String::from("#EXTM3U\n");
+    for (file, _) in files {
+        let name = &file[path.len()..];
+        if !is_audio(config, name) {
+            continue;
+        }
+        let title = name.rsplit('/').next().unwrap_or(name);
+        let title = title.rsplit_once('.').map_or(title, |(stem, _)| stem);
+        playlist.push_str(&format!(
+            "#EXTINF:-1,{}\n{origin}{}\n",
+            title.replace(['\r', '\n'], " "),
+            crate::href(&file)
+        ));
+    }
+
+    let directory = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let filename = if directory.is_empty() {
+        "playlist.m3u8".to_owned()
+    } else {
+        format!("{directory}.m3u8")
+    };
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "audio/x-mpegurl; charset=utf-8")?;
+    headers.set(
+        "content-disposition",
+        &crate::content_disposition::header("attachment", &filename),
+    )?;
+    Ok(worker::Response::ok(playlist)?.with_headers(headers))
+}
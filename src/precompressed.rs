@@ -0,0 +1,62 @@
+use crate::EntryType;
+
+const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+pub async fn get(
+    request: &worker::Request,
+    store: &dyn crate::store::ObjectStore,
+    key: &str,
+) -> worker::Result<Option<worker::Response>> {
+    let accept_encoding = request
+        .headers()
+        .get("accept-encoding")?
+        .unwrap_or_default();
+    let accepted: Vec<&str> = accept_encoding
+        .split(',')
+        .filter_map(|coding| {
+            let mut parameters = coding.split(';').map(str::trim);
+            let name = parameters.next()?;
+            let acceptable = parameters
+                .filter_map(|parameter| parameter.strip_prefix("q="))
+                .all(|quality| quality.parse::<f32>().map_or(true, |quality| quality > 0.0));
+            acceptable.then_some(name)
+        })
+        .collect();
+
+    for (encoding, suffix) in ENCODINGS {
+        if !accepted.contains(&encoding) {
+            continue;
+        }
+        let Some((variant, body)) = store.get(&format!("{key}.{suffix}")).await? else {
+            continue;
+        };
+        let Some(original) = store.head(key).await? else {
+            return Ok(None);
+        };
+
+        let mut headers = worker::Headers::new();
+        original.write_http_metadata(&mut headers)?;
+        variant.write_integrity_headers(&mut headers)?;
+        headers.set("content-length", &variant.size.to_string())?;
+        headers.set("content-encoding", encoding)?;
+        headers.set("vary", "accept-encoding")?;
+        return Ok(Some(body.into_response()?.with_headers(headers)));
+    }
+    Ok(None)
+}
+
+pub fn hide_variants(entries: &mut Vec<(EntryType, String)>) {
+    let files: std::collections::HashSet<String> = entries
+        .iter()
+        .filter(|(entry_type, _)| matches!(entry_type, EntryType::File { .. }))
+        .map(|(_, key)| key.clone())
+        .collect();
+    entries.retain(|(entry_type, key)| {
+        !matches!(entry_type, EntryType::File { .. })
+            || !ENCODINGS.iter().any(|(_, suffix)| {
+                key.strip_suffix(suffix)
+                    .and_then(|key| key.strip_suffix('.'))
+                    .is_some_and(|original| files.contains(original))
+            })
+    });
+}
@@ -0,0 +1,195 @@
+const CRAWLERS: [&str; 10] = [
+    "facebookexternalhit",
+    "twitterbot",
+    "slackbot",
+    "discordbot",
+    "linkedinbot",
+    "telegrambot",
+    "whatsapp",
+    "mastodon",
+    "redditbot",
+    "skypeuripreview",
+];
+
+const RENDERED: [&str; 10] = [
+    "image/",
+    "video/",
+    "audio/",
+    "text/",
+    "font/",
+    "application/pdf",
+    "application/json",
+    "application/xml",
+    "application/xhtml+xml",
+    "application/javascript",
+];
+
+markup::define! {
+    Preview<'a>(
+        name: &'a str,
+        url: &'a str,
+        description: &'a str,
+        site_name: &'a str,
+        image: bool,
+        exif: Option<crate::exif::Exif>,
+        font_url: Option<&'a str>
+    ) {
+        @markup::doctype()
+        html {
+            head {
+                meta[charset = "utf-8"] {}
+                title { @name }
+                meta[property = "og:title", content = name] {}
+                meta[property = "og:type", content = "website"] {}
+                meta[property = "og:url", content = url] {}
+                meta[property = "og:description", content = description] {}
+                meta[property = "og:site_name", content = site_name] {}
+                @if *image {
+                    meta[property = "og:image", content = url] {}
+                    meta[name = "twitter:card", content = "summary_large_image"] {}
+                } else {
+                    meta[name = "twitter:card", content = "summary"] {}
+                }
+                meta[name = "twitter:title", content = name] {}
+                meta[name = "twitter:description", content = description] {}
+                style {
+                    @crate::fonts::face(*font_url)
+                    "html { font-family: 'Inconsolata', monospace; }"
+                    "body { padding: 1em; }"
+                    "* { margin: 0; padding: 0; }"
+                    "header { margin-bottom: 2em; }"
+                    "p, img, dl { margin: 0 0 1em 1em; }"
+                    "dt { font-weight: bold; }"
+                    "dd { margin-left: 1em; }"
+                    "img { max-width: 100%; }"
+                }
+            }
+            body {
+                header {
+                    h1 { @name }
+                }
+                @if *image {
+                    img[src = url, alt = name] {}
+                }
+                p { @description }
+                @if let Some(exif) = exif {
+                    dl {
+                        @if let Some(camera) = &exif.camera {
+                            dt { "Camera" }
+                            dd { @camera }
+                        }
+                        @if let Some((width, height)) = exif.dimensions {
+                            dt { "Dimensions" }
+                            dd { @width "×" @height }
+                        }
+                        @if let Some(captured) = &exif.captured {
+                            dt { "Taken" }
+                            dd { @captured }
+                        }
+                        @if let Some((latitude, longitude)) = exif.location {
+                            dt { "Location" }
+                            dd { @format!("{latitude:.5}, {longitude:.5}") }
+                        }
+                    }
+                }
+                p {
+                    a[href = url] { "Open" }
+                    " · "
+                    a[href = format!("{url}?download")] { "Download" }
+                    " · "
+                    a[href = format!("{url}?qr")] { "QR code" }
+                }
+            }
+        }
+    }
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    store: &dyn crate::store::ObjectStore,
+    path: &str,
+    key: &str,
+) -> worker::Result<Option<worker::Response>> {
+    let url = request.url()?;
+    let explicit = url.query_pairs().any(|(name, _)| name == "preview");
+    if !explicit && !crawler(request)? {
+        return Ok(None);
+    }
+    let Some(object) = store.head(key).await? else {
+        return Ok(None);
+    };
+    let mut headers = worker::Headers::new();
+    object.write_http_metadata(&mut headers)?;
+    crate::mime::apply(config, key, &mut headers)?;
+    let content_type = headers.get("content-type")?.unwrap_or_default();
+    if !explicit && !for_crawlers(&content_type) {
+        return Ok(None);
+    }
+
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let description = format!(
+        "{} · uploaded {}",
+        humansize::format_size(object.size, crate::sizes::options(config, Some(&url))),
+        crate::dates::Dates::from_config(config).absolute(object.uploaded)
+    );
+    let exif = match content_type.starts_with("image/jpeg") && explicit {
+        true => crate::exif::read(config, store, key).await?,
+        false => None,
+    };
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/html")?;
+    headers.set("vary", "user-agent")?;
+    crate::security::page(config, &mut headers)?;
+    Ok(Some(
+        worker::Response::ok(
+            Preview {
+                name,
+                url: &format!(
+                    "{}{}",
+                    url.origin().ascii_serialization(),
+                    crate::href(path)
+                ),
+                description: &description,
+                site_name: url.host_str().unwrap_or_default(),
+                image: content_type.starts_with("image/"),
+                exif,
+                font_url: config.font_url.as_deref(),
+            }
+            .to_string(),
+        )?
+        .with_headers(headers),
+    ))
+}
+
+/// Whether crawlers get a preview page instead of the file, which only
+/// happens for downloads browsers would not render themselves.
+pub fn for_crawlers(content_type: &str) -> bool {
+    !RENDERED
+        .iter()
+        .any(|rendered| content_type.starts_with(rendered))
+}
+
+fn crawler(request: &worker::Request) -> worker::Result<bool> {
+    let user_agent = request
+        .headers()
+        .get("user-agent")?
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    Ok(CRAWLERS.iter().any(|crawler| user_agent.contains(crawler)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_only_downloads_for_crawlers() {
+        assert!(for_crawlers("application/zip"));
+        assert!(for_crawlers("application/octet-stream"));
+        assert!(for_crawlers(""));
+        assert!(!for_crawlers("text/html; charset=utf-8"));
+        assert!(!for_crawlers("application/pdf"));
+        assert!(!for_crawlers("image/png"));
+    }
+}
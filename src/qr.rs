@@ -0,0 +1,523 @@
+use itertools::Itertools;
+
+const QUIET_ZONE: usize = 4;
+
+const ECC_CODEWORDS_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+const ERROR_CORRECTION_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+pub async fn handle(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    store: &dyn crate::store::ObjectStore,
+    key: &str,
+) -> worker::Result<Option<worker::Response>> {
+    let mut url = request.url()?;
+    if !url.query_pairs().any(|(name, _)| name == "qr") {
+        return Ok(None);
+    }
+    if store.head(key).await?.is_none() {
+        return Ok(None);
+    }
+    let query = url
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            let name = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && name != "qr" && !crate::share::PARAMETERS.contains(&name)
+        })
+        .join("&");
+    url.set_query((!query.is_empty()).then_some(query.as_str()));
+    crate::share::sign(config, &mut url, worker::Date::now().as_millis() / 1000);
+
+    let Some(code) = Code::encode(url.as_str().as_bytes()) else {
+        return crate::errors::for_request(request, environment, config, 414, "URI Too Long")
+            .await
+            .map(Some);
+    };
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "image/svg+xml")?;
+    Ok(Some(
+        worker::Response::ok(code.svg())?.with_headers(headers),
+    ))
+}
+
+struct Code {
+    size: usize,
+    modules: Vec<Vec<bool>>,
+    function: Vec<Vec<bool>>,
+}
+
+impl Code {
+    fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=40).find(|&version| {
+            let count_bits = if version < 10 { 8 } else { 16 };
+            4 + count_bits + data.len() * 8 <= data_codewords(version) * 8
+        })?;
+
+        let mut bits = Vec::new();
+        push_bits(&mut bits, 0b0100, 4);
+        push_bits(&mut bits, data.len(), if version < 10 { 8 } else { 16 });
+        for &byte in data {
+            push_bits(&mut bits, byte.into(), 8);
+        }
+        let capacity = data_codewords(version) * 8;
+        let terminator = (capacity - bits.len()).min(4);
+        push_bits(&mut bits, 0, terminator);
+        let padding = (8 - bits.len() % 8) % 8;
+        push_bits(&mut bits, 0, padding);
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if bits.len() >= capacity {
+                break;
+            }
+            push_bits(&mut bits, pad, 8);
+        }
+        let codewords: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | u8::from(bit)))
+            .collect();
+
+        let size = version * 4 + 17;
+        let mut code = Self {
+            size,
+            modules: vec![vec![false; size]; size],
+            function: vec![vec![false; size]; size],
+        };
+        code.draw_function_patterns(version);
+        code.draw_codewords(&interleave(version, &codewords));
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                let mut candidate = Self {
+                    size,
+                    modules: code.modules.clone(),
+                    function: code.function.clone(),
+                };
+                candidate.apply_mask(mask);
+                candidate.draw_format_bits(mask);
+                candidate.penalty()
+            })
+            .expect("there are eight masks");
+        code.apply_mask(mask);
+        code.draw_format_bits(mask);
+        Some(code)
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y][x] = dark;
+        self.function[y][x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        for index in 0..self.size {
+            self.set_function(6, index, index % 2 == 0);
+            self.set_function(index, 6, index % 2 == 0);
+        }
+        let last = self.size - 4;
+        for (x, y) in [(3, 3), (last, 3), (3, last)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                    if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+        let positions = alignment_positions(version);
+        let count = positions.len();
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                let corner = |index| index == 0 || index == count - 1;
+                if corner(i) && corner(j) && (i == 0 || j == 0) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        self.set_function(
+                            (x as i32 + dx) as usize,
+                            (y as i32 + dy) as usize,
+                            dx.abs().max(dy.abs()) != 1,
+                        );
+                    }
+                }
+            }
+        }
+        self.draw_format_bits(0);
+        if version >= 7 {
+            let mut remainder = version;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let bits = version << 12 | remainder;
+            for index in 0..18 {
+                let dark = (bits >> index) & 1 == 1;
+                let (a, b) = (self.size - 11 + index % 3, index / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: usize) {
+        let data = mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |index: usize| (bits >> index) & 1 == 1;
+        for index in 0..=5 {
+            self.set_function(8, index, bit(index));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for index in 9..15 {
+            self.set_function(14 - index, 8, bit(index));
+        }
+        for index in 0..8 {
+            self.set_function(self.size - 1 - index, 8, bit(index));
+        }
+        for index in 8..15 {
+            self.set_function(8, self.size - 15 + index, bit(index));
+        }
+        self.set_function(8, self.size - 8, true);
+    }
+
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let mut index = 0;
+        let mut right = self.size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for offset in 0..2 {
+                    let x = (right - offset) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        self.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y][x] && index < codewords.len() * 8 {
+                        self.modules[y][x] = (codewords[index >> 3] >> (7 - (index & 7))) & 1 == 1;
+                        index += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: usize) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                self.modules[y][x] ^= invert && !self.function[y][x];
+            }
+        }
+    }
+
+    fn penalty(&self) -> usize {
+        const FINDER_LIKE: [bool; 11] = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+        let lines: Vec<Vec<bool>> = (0..self.size)
+            .map(|y| self.modules[y].clone())
+            .chain((0..self.size).map(|x| (0..self.size).map(|y| self.modules[y][x]).collect()))
+            .collect();
+        let mut penalty = 0;
+        for line in &lines {
+            let mut run = 1;
+            for index in 1..=line.len() {
+                if index < line.len() && line[index] == line[index - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+                run = 1;
+            }
+            penalty += line
+                .windows(FINDER_LIKE.len())
+                .filter(|window| {
+                    window.iter().eq(FINDER_LIKE.iter())
+                        || window.iter().eq(FINDER_LIKE.iter().rev())
+                })
+                .count()
+                * 40;
+        }
+        for y in 1..self.size {
+            for x in 1..self.size {
+                let dark = self.modules[y][x];
+                if self.modules[y - 1][x] == dark
+                    && self.modules[y][x - 1] == dark
+                    && self.modules[y - 1][x - 1] == dark
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let total = self.size * self.size;
+        let dark = self.modules.iter().flatten().filter(|&&dark| dark).count();
+        let k = ((dark * 20).abs_diff(total * 10) + total - 1) / total - 1;
+        penalty + k * 10
+    }
+
+    fn svg(&self) -> String {
+        let dimension = self.size + QUIET_ZONE * 2;
+        let mut path = String::new();
+        for (y, row) in self.modules.iter().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, &dark)| dark) {
+                path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+            }
+        }
+        format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {dimension} {dimension}\" shape-rendering=\"crispEdges\">",
+                "<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>",
+                "<path d=\"{path}\" fill=\"#000\"/>",
+                "</svg>\n",
+            ),
+            dimension = dimension,
+            path = path,
+        )
+    }
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: usize, length: usize) {
+    bits.extend((0..length).rev().map(|index| (value >> index) & 1 == 1));
+}
+
+fn raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        result -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[version] * ERROR_CORRECTION_BLOCKS[version]
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..count - 1)
+        .map(|index| size - 7 - index * step)
+        .collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+fn interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = ERROR_CORRECTION_BLOCKS[version];
+    let ecc_length = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_length = raw_codewords / blocks;
+    let divisor = reed_solomon_divisor(ecc_length);
+
+    let mut offset = 0;
+    let blocks: Vec<Vec<u8>> = (0..blocks)
+        .map(|index| {
+            let length = short_length - ecc_length + usize::from(index >= short_blocks);
+            let mut block = data[offset..offset + length].to_vec();
+            offset += length;
+            let ecc = reed_solomon_remainder(&block, &divisor);
+            if index < short_blocks {
+                block.push(0);
+            }
+            block.extend(ecc);
+            block
+        })
+        .collect();
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for index in 0..blocks[0].len() {
+        for (block_index, block) in blocks.iter().enumerate() {
+            if index != short_length - ecc_length || block_index >= short_blocks {
+                result.push(block[index]);
+            }
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for index in 0..degree {
+            result[index] = gf_multiply(result[index], root);
+            if index + 1 < degree {
+                result[index] ^= result[index + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (coefficient, value) in divisor.iter().zip(result.iter_mut()) {
+            *value ^= gf_multiply(*coefficient, factor);
+        }
+    }
+    result
+}
+
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for index in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= u16::from((y >> index) & 1) * u16::from(x);
+    }
+    z as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(code: &Code) -> Vec<String> {
+        code.modules
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&dark| if dark { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_a_reference_version_1_symbol() {
+        let code = Code::encode(b"https://a.io/").unwrap();
+        assert_eq!(rows(&code), VERSION_1);
+    }
+
+    #[test]
+    fn matches_a_reference_version_7_symbol() {
+        let code = Code::encode(LONG_URL.as_bytes()).unwrap();
+        assert_eq!(code.size, 45);
+        assert_eq!(rows(&code), VERSION_7);
+    }
+
+    #[test]
+    fn rejects_data_beyond_version_40() {
+        assert!(Code::encode(&[b'a'; 2331]).is_some());
+        assert!(Code::encode(&[b'a'; 2332]).is_none());
+    }
+
+    // Both symbols come from qrcode.js (Kazuhiko Arase) at level M with mask 2, the mask picked here.
+    const LONG_URL: &str = "https://files.example.com/releases/2024/app-1.2.3-linux-x86_64.tar.gz?download=app-1.2.3-linux-x86_64.tar.gz&view=preview";
+
+    const VERSION_1: [&str; 21] = [
+        "#######..#.#..#######",
+        "#.....#...#.#.#.....#",
+        "#.###.#.####..#.###.#",
+        "#.###.#.#.#...#.###.#",
+        "#.###.#.#.#.#.#.###.#",
+        "#.....#.##.#..#.....#",
+        "#######.#.#.#.#######",
+        "........#.#..........",
+        "#.#####....#..#####..",
+        ".##.#.......#########",
+        "..###.#.##..####..##.",
+        "..#..#.##..#.#..###..",
+        "..#..##.#.##..#.##..#",
+        "........##.....####.#",
+        "#######...#.##.#..##.",
+        "#.....#.#.##...#####.",
+        "#.###.#.####..####.##",
+        "#.###.#.#.#..##.#.#..",
+        "#.###.#.#.###.##..#..",
+        "#.....#..#.###..###..",
+        "#######.#.#...##.#.#.",
+    ];
+
+    const VERSION_7: [&str; 45] = [
+        "#######...###.#..###.##..#.#.##.##..#.#######",
+        "#.....#...###.####..##.#####...###.#..#.....#",
+        "#.###.#.#.#.......##.#.#.#.##.#.##.#..#.###.#",
+        "#.###.#.#...#..#.##.####.#...###.#.##.#.###.#",
+        "#.###.#.#.###.##...#######....#.#.###.#.###.#",
+        "#.....#.###..#.#.##.#...###.#..###....#.....#",
+        "#######.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#######",
+        "........##.##.#.##.##...######..#..##........",
+        "#.#####..#######..#.#####....#.#.##...#####..",
+        ".....#..#..##.#...##.#...#.#.##....##...#.###",
+        "########.#...##.##.######.##...#####.###.#.#.",
+        ".####..#...#......##.#...########.##....###..",
+        "...#..#####...######.##.#....#....#...##....#",
+        "#..###.###...##....#...#.#...###....#..#.#.##",
+        ".#....#####.###.##..#...#.####.####.#####..#.",
+        ".###.#...###..#..#.#........###.#..#########.",
+        "..##.###..#.##.#..##..##.....##..#....##.#.#.",
+        "...##..#.######..#.##.##.....####..###.##...#",
+        "....####.#.#.##.#..##..##.##.#...###..##..#..",
+        "#.#.#..#.##.##..###..#.###.###..#.##.#..#.#..",
+        ".##.#####.##.###.##.########.....########..#.",
+        "###.#...####.##.#...#...#...#.#.#..##...#####",
+        "...##.#.#.....#..#..#.#.####.#....#.#.#.#.##.",
+        ".####...#..#.#.#...##...##..#.#.###.#...####.",
+        "#.#.#####.#..#..##..######...###....######...",
+        "...##...#.#..#.#..#######....##.#..#..##.##.#",
+        "......##.##..##.###...#...#.#..####..#..####.",
+        ".##.#..#.##.#...#..#...##.###...##.###..####.",
+        "#.#..#######.....#.....#.#..........#.#.##..#",
+        "#..#.#.####.#.#.#####.#.##.##.#....##.#..####",
+        "#.##..#####.#####...#.##..#.......##...#..##.",
+        ".####....#.##.####...########..##.###.##.##.#",
+        ".##.#########.#.#..#.#..##....##..#.#...#..##",
+        ".#.....#...#.#.#####..#.#....###...##..#.#.##",
+        "....#.#.##.#.#.##...#....##.#..#..#.#..#.##..",
+        ".####...##..#.##..##.##.###.#.###..########.#",
+        "#..##.#.#...##..##..######.#...#.#..######.#.",
+        "........#..#.....####...##.##.##...##...###.#",
+        "#######..#......#..##.#.###.#....####.#.#.##.",
+        "#.....#.######.##.#.#...#######.#.###...####.",
+        "#.###.#.#...#..####.#####.#...##.#.#######.#.",
+        "#.###.#.#.###.#.#.#....###....###..#.#..#.###",
+        "#.###.#.#....#.#.##.###.#.##.#.#.##.###..#.#.",
+        "#.....#..##.#.#.##.##.##.##.######.#.#....#..",
+        "#######.###....#...#..###.#....#.#.#.###...#.",
+    ];
+}
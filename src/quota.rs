@@ -0,0 +1,115 @@
+use worker::{async_trait, js_sys, wasm_bindgen, wasm_bindgen_futures, worker_sys};
+
+const BINDING: &str = "QUOTA";
+const SLOTS_PER_WINDOW: u64 = 24;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Outcome {
+    allowed: bool,
+    retry_after: u64,
+}
+
+#[worker::durable_object]
+pub struct Quota {
+    state: worker::State,
+}
+
+#[worker::durable_object]
+impl DurableObject for Quota {
+    fn new(state: worker::State, _environment: worker::Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, request: worker::Request) -> worker::Result<worker::Response> {
+        let url = request.url()?;
+        let parameter = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .and_then(|(_, value)| value.parse::<u64>().ok())
+                .unwrap_or_default()
+        };
+        let (bytes, budget) = (parameter("bytes"), parameter("budget"));
+        let window = parameter("window").max(SLOTS_PER_WINDOW) * 1000;
+        let slot_length = window / SLOTS_PER_WINDOW;
+        let now = worker::Date::now().as_millis();
+
+        let mut storage = self.state.storage();
+        let mut slots: Vec<(u64, u64)> = storage.get("slots").await.unwrap_or_default();
+        slots.retain(|(start, _)| start + window > now);
+        let used: u64 = slots.iter().map(|(_, bytes)| bytes).sum();
+        if used >= budget {
+            let retry_after = slots
+                .first()
+                .map(|(start, _)| (start + window).saturating_sub(now) / 1000 + 1)
+                .unwrap_or_default();
+            return worker::Response::from_json(&Outcome {
+                allowed: false,
+                retry_after,
+            });
+        }
+        let slot = now - now % slot_length;
+        match slots.last_mut() {
+            Some((start, total)) if *start == slot => *total += bytes,
+            _ => slots.push((slot, bytes)),
+        }
+        storage.put("slots", slots).await?;
+        worker::Response::from_json(&Outcome {
+            allowed: true,
+            retry_after: 0,
+        })
+    }
+}
+
+pub async fn consume(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    context: &worker::Context,
+    key: &str,
+    bytes: u64,
+) -> worker::Result<Option<worker::Response>> {
+    let Some(budget) = config.quota_bytes else {
+        return Ok(None);
+    };
+    let Ok(namespace) = environment.durable_object(BINDING) else {
+        return Ok(None);
+    };
+    let directory = key.rfind('/').map_or("", |index| &key[..=index]);
+    let prefix = match config.quota_depth {
+        0 => "",
+        depth => key
+            .match_indices('/')
+            .nth(depth - 1)
+            .map_or(directory, |(index, _)| &key[..=index]),
+    };
+    let stub = namespace.id_from_name(prefix)?.get_stub()?;
+    let outcome: Outcome = stub
+        .fetch_with_str(&format!(
+            "https://quota/consume?bytes={bytes}&budget={budget}&window={}",
+            config.quota_window
+        ))
+        .await?
+        .json()
+        .await?;
+    if outcome.allowed {
+        return Ok(None);
+    }
+
+    crate::webhooks::notify(
+        config,
+        context,
+        request,
+        crate::webhooks::Event::QuotaExceeded,
+    )?;
+    let status = config.quota_status;
+    let message = if status == 403 {
+        "Forbidden"
+    } else {
+        "Too Many Requests"
+    };
+    let response =
+        crate::errors::for_request(request, environment, config, status, message).await?;
+    let mut headers = response.headers().clone();
+    headers.set("retry-after", &outcome.retry_after.to_string())?;
+    Ok(Some(response.with_headers(headers)))
+}
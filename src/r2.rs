@@ -0,0 +1,198 @@
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use worker::js_sys::{self, Reflect};
+use worker::wasm_bindgen::{JsCast, JsValue};
+use worker::wasm_bindgen_futures::JsFuture;
+use worker::worker_sys::{R2Bucket, R2Object, R2ObjectBody};
+
+use crate::store::{Body, ListOptions, Listing, Object, ObjectStore};
+
+impl ObjectStore for worker::Bucket {
+    fn list(&self, options: ListOptions) -> LocalBoxFuture<'_, worker::Result<Listing>> {
+        async move {
+            let js_options = js_sys::Object::new();
+            set(&js_options, "prefix", options.prefix.into());
+            if let Some(delimiter) = options.delimiter {
+                set(&js_options, "delimiter", delimiter.into());
+            }
+            if let Some(cursor) = options.cursor {
+                set(&js_options, "cursor", cursor.into());
+            }
+            if let Some(limit) = options.limit {
+                set(&js_options, "limit", limit.into());
+            }
+            if options.include_metadata {
+                let include = js_sys::Array::of2(&"httpMetadata".into(), &"customMetadata".into());
+                set(&js_options, "include", include.into());
+            }
+
+            let inner = JsFuture::from(bucket(self).list(js_options.into())).await?;
+            let truncated = get(&inner, "truncated").is_truthy();
+            Ok(Listing {
+                objects: js_sys::Array::from(&get(&inner, "objects"))
+                    .iter()
+                    .map(|inner| object(&inner))
+                    .collect::<worker::Result<_>>()?,
+                delimited_prefixes: js_sys::Array::from(&get(&inner, "delimitedPrefixes"))
+                    .iter()
+                    .filter_map(|prefix| prefix.as_string())
+                    .collect(),
+                cursor: truncated
+                    .then(|| get(&inner, "cursor").as_string())
+                    .flatten(),
+            })
+        }
+        .boxed_local()
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, worker::Result<Option<Object>>> {
+        async move {
+            let inner = JsFuture::from(bucket(self).head(key.to_owned())).await?;
+            if inner.is_null() || inner.is_undefined() {
+                return Ok(None);
+            }
+            object(&inner).map(Some)
+        }
+        .boxed_local()
+    }
+
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> LocalBoxFuture<'a, worker::Result<Option<(Object, Body)>>> {
+        async move {
+            let inner =
+                JsFuture::from(bucket(self).get(key.to_owned(), JsValue::UNDEFINED)).await?;
+            if inner.is_null() || inner.is_undefined() {
+                return Ok(None);
+            }
+            let body = Body::Stream(inner.unchecked_ref::<R2ObjectBody>().body());
+            Ok(Some((object(&inner)?, body)))
+        }
+        .boxed_local()
+    }
+
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        offset: u64,
+        length: u64,
+    ) -> LocalBoxFuture<'a, worker::Result<Option<(Object, Body)>>> {
+        async move {
+            let range = js_sys::Object::new();
+            set(&range, "offset", (offset as f64).into());
+            set(&range, "length", (length as f64).into());
+            let js_options = js_sys::Object::new();
+            set(&js_options, "range", range.into());
+            let inner = JsFuture::from(bucket(self).get(key.to_owned(), js_options.into())).await?;
+            if inner.is_null() || inner.is_undefined() {
+                return Ok(None);
+            }
+            let body = Body::Stream(inner.unchecked_ref::<R2ObjectBody>().body());
+            Ok(Some((object(&inner)?, body)))
+        }
+        .boxed_local()
+    }
+
+    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> LocalBoxFuture<'a, worker::Result<()>> {
+        async move {
+            let value = js_sys::Uint8Array::from(value.as_slice());
+            JsFuture::from(bucket(self).put(key.to_owned(), value.into(), JsValue::UNDEFINED))
+                .await?;
+            Ok(())
+        }
+        .boxed_local()
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, worker::Result<()>> {
+        async move {
+            JsFuture::from(bucket(self).delete(key.to_owned())).await?;
+            Ok(())
+        }
+        .boxed_local()
+    }
+
+    fn rename<'a>(
+        &'a self,
+        from: &'a str,
+        to: &'a str,
+    ) -> LocalBoxFuture<'a, worker::Result<bool>> {
+        async move {
+            let inner =
+                JsFuture::from(bucket(self).get(from.to_owned(), JsValue::UNDEFINED)).await?;
+            if inner.is_null() || inner.is_undefined() {
+                return Ok(false);
+            }
+            let js_options = js_sys::Object::new();
+            set(&js_options, "httpMetadata", get(&inner, "httpMetadata"));
+            set(&js_options, "customMetadata", get(&inner, "customMetadata"));
+            let body = inner.unchecked_ref::<R2ObjectBody>().body();
+            JsFuture::from(bucket(self).put(to.to_owned(), body.into(), js_options.into())).await?;
+            JsFuture::from(bucket(self).delete(from.to_owned())).await?;
+            Ok(true)
+        }
+        .boxed_local()
+    }
+}
+
+fn bucket(bucket: &worker::Bucket) -> &R2Bucket {
+    bucket.as_ref().unchecked_ref()
+}
+
+fn object(inner: &JsValue) -> worker::Result<Object> {
+    let millis = get(inner, "uploaded")
+        .dyn_into::<js_sys::Date>()
+        .map(|date| date.get_time())
+        .unwrap_or_default();
+    let headers = worker::Headers::new();
+    inner
+        .unchecked_ref::<R2Object>()
+        .write_http_metadata(headers.0.clone())?;
+    Ok(Object {
+        key: get(inner, "key").as_string().unwrap_or_default(),
+        size: get(inner, "size").as_f64().unwrap_or_default() as u64,
+        uploaded: chrono::NaiveDateTime::from_timestamp_millis(millis as i64)
+            .expect("must be valid")
+            .and_utc(),
+        http_metadata: headers.entries().collect(),
+        etag: get(inner, "httpEtag").as_string().unwrap_or_default(),
+        checksums: checksums(&get(inner, "checksums")),
+        custom_metadata: custom_metadata(&get(inner, "customMetadata")),
+    })
+}
+
+fn checksums(inner: &JsValue) -> Vec<(String, String)> {
+    crate::store::CHECKSUM_ALGORITHMS
+        .iter()
+        .filter_map(|algorithm| {
+            let buffer = get(inner, algorithm)
+                .dyn_into::<js_sys::ArrayBuffer>()
+                .ok()?;
+            Some((
+                algorithm.to_string(),
+                crate::hex(&js_sys::Uint8Array::new(&buffer).to_vec()),
+            ))
+        })
+        .collect()
+}
+
+fn custom_metadata(inner: &JsValue) -> Vec<(String, String)> {
+    let Some(inner) = inner.dyn_ref::<js_sys::Object>() else {
+        return Vec::new();
+    };
+    js_sys::Object::entries(inner)
+        .iter()
+        .filter_map(|entry| {
+            let entry = js_sys::Array::from(&entry);
+            Some((entry.get(0).as_string()?, entry.get(1).as_string()?))
+        })
+        .collect()
+}
+
+fn set(target: &js_sys::Object, name: &str, value: JsValue) {
+    Reflect::set(target, &name.into(), &value)
+        .expect("setting properties never fails on plain objects");
+}
+
+fn get(target: &JsValue, name: &str) -> JsValue {
+    Reflect::get(target, &name.into()).unwrap_or(JsValue::UNDEFINED)
+}
@@ -0,0 +1,248 @@
+use futures_util::StreamExt;
+
+const MAX_RANGES: usize = 16;
+
+pub struct Range {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Range {
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+pub enum Ranges {
+    Full,
+    Unsatisfiable,
+    Partial(Vec<Range>),
+}
+
+pub fn parse(header: &str, size: u64) -> Ranges {
+    let Some(specs) = header.trim().strip_prefix("bytes=") else {
+        return Ranges::Full;
+    };
+    let specs: Vec<&str> = specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return Ranges::Full;
+    }
+    let mut ranges = Vec::new();
+    for spec in specs {
+        let Some((start, end)) = spec.split_once('-') else {
+            return Ranges::Full;
+        };
+        let (start, end) = match (start.trim(), end.trim()) {
+            ("", "") => return Ranges::Full,
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(suffix) => (size.saturating_sub(suffix), size.checked_sub(1)),
+                Err(_) => return Ranges::Full,
+            },
+            (start, "") => match start.parse::<u64>() {
+                Ok(start) => (start, size.checked_sub(1)),
+                Err(_) => return Ranges::Full,
+            },
+            (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+                (Ok(start), Ok(end)) if start <= end => {
+                    (start, size.checked_sub(1).map(|last| end.min(last)))
+                }
+                _ => return Ranges::Full,
+            },
+        };
+        if let Some(end) = end.filter(|end| start <= *end) {
+            ranges.push(Range { start, end });
+        }
+    }
+    if ranges.is_empty() {
+        Ranges::Unsatisfiable
+    } else {
+        Ranges::Partial(ranges)
+    }
+}
+
+pub fn matches(if_range: &str, object: &crate::store::Object) -> bool {
+    let if_range = if_range.trim();
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return !object.etag.is_empty() && if_range == object.etag;
+    }
+    chrono::DateTime::parse_from_rfc2822(if_range)
+        .is_ok_and(|date| date.timestamp() == object.uploaded.timestamp())
+}
+
+fn part_head(boundary: &str, content_type: &str, range: &Range, size: u64) -> String {
+    format!(
+        "--{boundary}\r\ncontent-type: {content_type}\r\ncontent-range: bytes {}-{}/{size}\r\n\r\n",
+        range.start, range.end
+    )
+}
+
+fn tail(boundary: &str) -> String {
+    format!("--{boundary}--\r\n")
+}
+
+pub fn unsatisfiable(size: u64) -> worker::Result<worker::Response> {
+    let mut headers = worker::Headers::new();
+    headers.set("content-range", &format!("bytes */{size}"))?;
+    Ok(worker::Response::empty()?
+        .with_status(416)
+        .with_headers(headers))
+}
+
+pub async fn serve(
+    store: &dyn crate::store::ObjectStore,
+    object: &crate::store::Object,
+    ranges: &[Range],
+    mut headers: worker::Headers,
+) -> worker::Result<Option<worker::Response>> {
+    if let [range] = ranges {
+        let Some((_, body)) = store
+            .get_range(&object.key, range.start, range.length())
+            .await?
+        else {
+            return Ok(None);
+        };
+        headers.set(
+            "content-range",
+            &format!("bytes {}-{}/{}", range.start, range.end, object.size),
+        )?;
+        headers.set("content-length", &range.length().to_string())?;
+        return Ok(Some(
+            body.into_response()?.with_status(206).with_headers(headers),
+        ));
+    }
+
+    let boundary = crate::random_hex(16);
+    let content_type = headers
+        .get("content-type")?
+        .unwrap_or_else(|| "application/octet-stream".into());
+    let mut parts = Vec::new();
+    let mut length = 0;
+    for range in ranges {
+        let Some((_, body)) = store
+            .get_range(&object.key, range.start, range.length())
+            .await?
+        else {
+            return Ok(None);
+        };
+        let head = part_head(&boundary, &content_type, range, object.size);
+        length += head.len() as u64 + range.length() + 2;
+        parts.push(
+            futures_util::stream::iter([Ok(head.into_bytes())])
+                .chain(body.into_response()?.stream()?)
+                .chain(futures_util::stream::iter([Ok(b"\r\n".to_vec())]))
+                .boxed_local(),
+        );
+    }
+    let tail = tail(&boundary);
+    length += tail.len() as u64;
+    parts.push(futures_util::stream::iter([Ok(tail.into_bytes())]).boxed_local());
+    headers.set(
+        "content-type",
+        &format!("multipart/byteranges; boundary={boundary}"),
+    )?;
+    headers.set("content-length", &length.to_string())?;
+    Ok(Some(
+        worker::Response::from_stream(futures_util::stream::iter(parts).flatten())?
+            .with_status(206)
+            .with_headers(headers),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+    use crate::store::ObjectStore;
+
+    fn spans(ranges: Ranges) -> Option<Vec<(u64, u64)>> {
+        match ranges {
+            Ranges::Full => None,
+            Ranges::Unsatisfiable => Some(Vec::new()),
+            Ranges::Partial(ranges) => Some(
+                ranges
+                    .into_iter()
+                    .map(|range| (range.start, range.end))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(spans(parse("bytes=0-9", 100)), Some(vec![(0, 9)]));
+        assert_eq!(spans(parse("bytes=90-", 100)), Some(vec![(90, 99)]));
+        assert_eq!(spans(parse("bytes=-10", 100)), Some(vec![(90, 99)]));
+        assert_eq!(spans(parse("bytes=-200", 100)), Some(vec![(0, 99)]));
+        assert_eq!(spans(parse("bytes=50-500", 100)), Some(vec![(50, 99)]));
+        assert_eq!(
+            spans(parse("bytes=0-1, 5-6", 100)),
+            Some(vec![(0, 1), (5, 6)])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_and_unsatisfiable_ranges() {
+        assert_eq!(spans(parse("items=0-9", 100)), None);
+        assert_eq!(spans(parse("bytes=9-0", 100)), None);
+        assert_eq!(spans(parse("bytes=a-b", 100)), None);
+        assert_eq!(spans(parse("bytes=-", 100)), None);
+        let too_many = format!("bytes={}", vec!["0-0"; MAX_RANGES + 1].join(","));
+        assert_eq!(spans(parse(&too_many, 100)), None);
+        assert_eq!(spans(parse("bytes=100-", 100)), Some(Vec::new()));
+        assert_eq!(spans(parse("bytes=0-", 0)), Some(Vec::new()));
+    }
+
+    #[test]
+    fn matches_if_range_validators() {
+        let store = crate::store::MemoryStore::with_files(&[("file", "contents")]);
+        let object = store.head("file").now_or_never().unwrap().unwrap().unwrap();
+        assert!(matches(&object.etag, &object));
+        assert!(!matches("\"other\"", &object));
+        assert!(!matches(&format!("W/{}", object.etag), &object));
+        assert!(matches(&object.uploaded.to_rfc2822(), &object));
+        let earlier = object.uploaded - chrono::Duration::seconds(1);
+        assert!(!matches(&earlier.to_rfc2822(), &object));
+        assert!(!matches("yesterday", &object));
+    }
+
+    #[test]
+    fn counts_multipart_lengths() {
+        let store = crate::store::MemoryStore::with_files(&[("file", "0123456789")]);
+        let Ranges::Partial(ranges) = parse("bytes=0-1,-3", 10) else {
+            panic!("the ranges must be satisfiable");
+        };
+        let mut body = Vec::new();
+        let mut length = 0;
+        for range in &ranges {
+            let head = part_head("b", "text/plain", range, 10);
+            let (_, part) = store
+                .get_range("file", range.start, range.length())
+                .now_or_never()
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let crate::store::Body::Bytes(part) = part else {
+                panic!("the memory store must return bytes");
+            };
+            length += head.len() as u64 + range.length() + 2;
+            body.extend(head.into_bytes());
+            body.extend(part);
+            body.extend(b"\r\n");
+        }
+        let tail = tail("b");
+        length += tail.len() as u64;
+        body.extend(tail.into_bytes());
+        assert_eq!(body.len() as u64, length);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--b\r\ncontent-type: text/plain\r\ncontent-range: bytes 0-1/10\r\n\r\n01\r\n\
+             --b\r\ncontent-type: text/plain\r\ncontent-range: bytes 7-9/10\r\n\r\n789\r\n\
+             --b--\r\n"
+        );
+    }
+}
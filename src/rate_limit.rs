@@ -0,0 +1,40 @@
+use worker::js_sys::{self, Reflect};
+use worker::wasm_bindgen::JsCast;
+use worker::wasm_bindgen_futures::JsFuture;
+
+pub const LISTINGS_BINDING: &str = "RATE_LIMITER_LISTINGS";
+pub const DOWNLOADS_BINDING: &str = "RATE_LIMITER_DOWNLOADS";
+
+pub async fn check(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    binding: &str,
+) -> worker::Result<Option<worker::Response>> {
+    let limiter = Reflect::get(environment, &binding.into())?;
+    if limiter.is_undefined() {
+        return Ok(None);
+    }
+    let limit: js_sys::Function = Reflect::get(&limiter, &"limit".into())?.dyn_into()?;
+    let key = request
+        .headers()
+        .get("cf-connecting-ip")?
+        .unwrap_or_default();
+    let options = js_sys::Object::new();
+    Reflect::set(&options, &"key".into(), &key.into())?;
+    let outcome = JsFuture::from(
+        limit
+            .call1(&limiter, &options)?
+            .dyn_into::<js_sys::Promise>()?,
+    )
+    .await?;
+    if Reflect::get(&outcome, &"success".into())?.is_truthy() {
+        return Ok(None);
+    }
+
+    let response =
+        crate::errors::for_request(request, environment, config, 429, "Too Many Requests").await?;
+    let mut headers = response.headers().clone();
+    headers.set("retry-after", &config.rate_limit_retry_after.to_string())?;
+    Ok(Some(response.with_headers(headers)))
+}
@@ -0,0 +1,70 @@
+pub enum Resolution {
+    Redirect { location: String, status: u16 },
+    Rewrite { path: String },
+}
+
+struct Rule {
+    source: String,
+    destination: String,
+    status: u16,
+}
+
+pub async fn resolve(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<Option<Resolution>> {
+    let Some(key) = &config.redirects_file else {
+        return Ok(None);
+    };
+    let rules = crate::bucket_file::load(request, environment, config, key, parse).await?;
+    let url = request.url()?;
+
+    let path = url.path();
+    Ok(rules.iter().find_map(|rule| {
+        let captures = crate::pattern::matches(&rule.source, path)?;
+        let mut destination = crate::pattern::substitute(&rule.destination, captures);
+        if rule.status == 200 {
+            return Some(Resolution::Rewrite { path: destination });
+        }
+        if let Some(query) = url.query().filter(|_| !destination.contains('?')) {
+            destination = format!("{destination}?{query}");
+        }
+        Some(Resolution::Redirect {
+            location: destination,
+            status: rule.status,
+        })
+    }))
+}
+
+fn parse(contents: &str) -> Vec<Rule> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?.to_owned();
+            let destination = fields.next()?.to_owned();
+            let status = match fields.next() {
+                Some(status) => status.trim_end_matches('!').parse().ok(),
+                None => Some(302),
+            };
+            let status = match status {
+                Some(status @ (301 | 302 | 303 | 307 | 308)) => status,
+                Some(200) if destination.starts_with('/') => 200,
+                _ => {
+                    worker::console_warn!(
+                        "ignoring _redirects rule with an unsupported status: {line}"
+                    );
+                    return None;
+                }
+            };
+            Some(Rule {
+                source,
+                destination,
+                status,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+
+use worker::js_sys::{self, Reflect};
+use worker::wasm_bindgen::{JsCast, JsValue};
+use worker::wasm_bindgen_futures::JsFuture;
+
+const SENTRY_CLIENT: &str = "r2-directory-listing/1.0";
+
+#[derive(Clone)]
+enum Sink {
+    Sentry {
+        dsn: String,
+        endpoint: String,
+        key: String,
+    },
+    Webhook(String),
+}
+
+#[derive(Clone, serde::Serialize)]
+struct Context {
+    url: String,
+    method: String,
+    ray: Option<String>,
+    request_id: String,
+}
+
+#[derive(serde::Serialize)]
+struct WebhookBody<'a> {
+    message: &'a str,
+    #[serde(flatten)]
+    context: Option<&'a Context>,
+}
+
+#[derive(Clone)]
+pub struct Reporter {
+    sink: Sink,
+    context: Option<Context>,
+}
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = RefCell::new(None);
+}
+
+impl Reporter {
+    pub fn new(
+        config: &crate::config::Config,
+        request: &worker::Request,
+        request_id: &str,
+    ) -> worker::Result<Option<Self>> {
+        let sink = match (&config.sentry_dsn, &config.error_webhook_url) {
+            (Some(dsn), _) => Sink::Sentry {
+                dsn: dsn.to_string(),
+                endpoint: format!(
+                    "{}://{}{}/api/{}/envelope/",
+                    dsn.scheme(),
+                    dsn.host_str().unwrap_or_default(),
+                    dsn.port()
+                        .map(|port| format!(":{port}"))
+                        .unwrap_or_default(),
+                    dsn.path().trim_matches('/')
+                ),
+                key: dsn.username().to_owned(),
+            },
+            (None, Some(url)) => Sink::Webhook(url.to_string()),
+            (None, None) => return Ok(None),
+        };
+        SINK.with(|current| current.replace(Some(sink.clone())));
+        Ok(Some(Self {
+            sink,
+            context: Some(Context {
+                url: request.url()?.to_string(),
+                method: request.method().to_string(),
+                ray: request.headers().get("cf-ray")?,
+                request_id: request_id.to_owned(),
+            }),
+        }))
+    }
+
+    pub async fn report(&self, message: &str) -> worker::Result<()> {
+        JsFuture::from(self.dispatch(message)?).await?;
+        Ok(())
+    }
+
+    fn dispatch(&self, message: &str) -> worker::Result<js_sys::Promise> {
+        let headers = js_sys::Object::new();
+        let (endpoint, body) = match &self.sink {
+            Sink::Sentry { dsn, endpoint, key } => {
+                let auth = format!(
+                    "Sentry sentry_version=7, sentry_key={key}, sentry_client={SENTRY_CLIENT}"
+                );
+                Reflect::set(&headers, &"x-sentry-auth".into(), &auth.into())?;
+                Reflect::set(
+                    &headers,
+                    &"content-type".into(),
+                    &"application/x-sentry-envelope".into(),
+                )?;
+                (endpoint, self.envelope(dsn, message))
+            }
+            Sink::Webhook(url) => {
+                Reflect::set(&headers, &"content-type".into(), &"application/json".into())?;
+                let body = WebhookBody {
+                    message,
+                    context: self.context.as_ref(),
+                };
+                (url, serde_json::to_string(&body)?)
+            }
+        };
+
+        let init = js_sys::Object::new();
+        Reflect::set(&init, &"method".into(), &"POST".into())?;
+        Reflect::set(&init, &"headers".into(), &headers)?;
+        Reflect::set(&init, &"body".into(), &body.into())?;
+        let global = js_sys::global();
+        let fetch: js_sys::Function = Reflect::get(&global, &"fetch".into())?.dyn_into()?;
+        Ok(fetch
+            .call2(&global, &JsValue::from(endpoint.as_str()), &init)?
+            .dyn_into()?)
+    }
+
+    fn envelope(&self, dsn: &str, message: &str) -> String {
+        let event_id = crate::random_hex(16);
+        let timestamp =
+            chrono::NaiveDateTime::from_timestamp_millis(worker::Date::now().as_millis() as i64)
+                .expect("must be valid")
+                .and_utc()
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let header = serde_json::json!({ "event_id": event_id, "sent_at": timestamp, "dsn": dsn });
+        let mut event = serde_json::json!({
+            "event_id": event_id,
+            "timestamp": timestamp,
+            "platform": "other",
+            "level": "error",
+            "logger": "worker",
+            "message": { "formatted": message },
+        });
+        if let Some(context) = &self.context {
+            event["request"] = serde_json::json!({ "url": context.url, "method": context.method });
+            event["tags"] =
+                serde_json::json!({ "ray": context.ray, "request_id": context.request_id });
+        }
+        format!("{header}\n{{\"type\":\"event\"}}\n{event}\n")
+    }
+}
+
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        console_error_panic_hook::hook(info);
+        if let Some(sink) = SINK.with(|current| current.borrow().clone()) {
+            let reporter = Reporter {
+                sink,
+                context: None,
+            };
+            let _ = reporter.dispatch(&info.to_string());
+        }
+    }));
+}
@@ -0,0 +1,19 @@
+pub const PATH: &str = "robots.txt";
+
+pub fn response(config: &crate::config::Config) -> worker::Result<Option<worker::Response>> {
+    let Some(robots_txt) = &config.robots_txt else {
+        return Ok(None);
+    };
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/plain; charset=utf-8")?;
+    Ok(Some(
+        worker::Response::ok(robots_txt.replace("\\n", "\n"))?.with_headers(headers),
+    ))
+}
+
+pub fn tag(tag: &Option<String>, headers: &mut worker::Headers) -> worker::Result<()> {
+    match tag {
+        Some(tag) => headers.set("x-robots-tag", tag),
+        None => Ok(()),
+    }
+}
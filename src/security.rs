@@ -0,0 +1,50 @@
+pub fn apply(
+    config: &crate::config::Config,
+    response: worker::Response,
+) -> worker::Result<worker::Response> {
+    let is_html = response
+        .headers()
+        .get("content-type")?
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if !is_html {
+        return Ok(response);
+    }
+    let mut headers = response.headers().clone();
+    for (name, value) in [
+        ("x-content-type-options", &config.content_type_options),
+        ("referrer-policy", &config.referrer_policy),
+        (
+            "strict-transport-security",
+            &config.strict_transport_security,
+        ),
+    ] {
+        if let Some(value) = value {
+            if !headers.has(name)? {
+                headers.set(name, value)?;
+            }
+        }
+    }
+    Ok(response.with_headers(headers))
+}
+
+pub fn page(config: &crate::config::Config, headers: &mut worker::Headers) -> worker::Result<()> {
+    if let Some(policy) = &config.content_security_policy {
+        if !headers.has("content-security-policy")? {
+            headers.set("content-security-policy", policy)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn with_frame_ancestors(config: &crate::config::Config, sources: &str) -> String {
+    config
+        .content_security_policy
+        .iter()
+        .flat_map(|policy| policy.split(';'))
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty() && !directive.starts_with("frame-ancestors"))
+        .map(str::to_owned)
+        .chain(std::iter::once(format!("frame-ancestors {sources}")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
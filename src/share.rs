@@ -0,0 +1,101 @@
+use hmac::Mac;
+
+type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+pub const PARAMETERS: [&str; 2] = ["expires", "signature"];
+
+pub fn sign(config: &crate::config::Config, url: &mut url::Url, now: u64) {
+    let Some(key) = &config.share_signing_key else {
+        return;
+    };
+    let expires = now + config.share_ttl;
+    let signature = crate::hex(&mac(key, url.path(), expires).finalize().into_bytes());
+    url.query_pairs_mut()
+        .append_pair("expires", &expires.to_string())
+        .append_pair("signature", &signature);
+}
+
+pub fn verify(config: &crate::config::Config, url: &url::Url, now: u64) -> bool {
+    let Some(key) = &config.share_signing_key else {
+        return false;
+    };
+    let parameter = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let (Some(expires), Some(signature)) = (parameter("expires"), parameter("signature")) else {
+        return false;
+    };
+    let Ok(expires) = expires.parse::<u64>() else {
+        return false;
+    };
+    expires > now
+        && crate::unhex(&signature).is_some_and(|signature| {
+            mac(key, url.path(), expires)
+                .verify_slice(&signature)
+                .is_ok()
+        })
+}
+
+fn mac(key: &str, path: &str, expires: u64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{path}\n{expires}").as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> crate::config::Config {
+        crate::config::Config::from_vars(&[("SHARE_SIGNING_KEY", "secret"), ("SHARE_TTL", "60")])
+    }
+
+    fn signed(path: &str) -> url::Url {
+        let mut url = url::Url::parse(&format!("https://files.example{path}")).unwrap();
+        sign(&config(), &mut url, 1000);
+        url
+    }
+
+    #[test]
+    fn accepts_signed_urls_until_they_expire() {
+        let url = signed("/docs/a.txt?download");
+        assert!(url
+            .query()
+            .unwrap()
+            .starts_with("download&expires=1060&signature="));
+        assert!(verify(&config(), &url, 1059));
+        assert!(!verify(&config(), &url, 1060));
+    }
+
+    #[test]
+    fn rejects_other_paths_and_keys() {
+        let mut url = signed("/docs/a.txt");
+        let other = crate::config::Config::from_vars(&[("SHARE_SIGNING_KEY", "other")]);
+        assert!(!verify(&other, &url, 1000));
+        assert!(!verify(&Default::default(), &url, 1000));
+        url.set_path("/docs/b.txt");
+        assert!(!verify(&config(), &url, 1000));
+    }
+
+    #[test]
+    fn rejects_tampered_expiry() {
+        let url = signed("/a.txt");
+        let query = url.query().unwrap().replace("expires=1060", "expires=9999");
+        let mut tampered = url.clone();
+        tampered.set_query(Some(&query));
+        assert!(!verify(&config(), &tampered, 1000));
+        let mut unsigned = url;
+        unsigned.set_query(None);
+        assert!(!verify(&config(), &unsigned, 1000));
+    }
+
+    #[test]
+    fn leaves_urls_alone_without_a_key() {
+        let mut url = url::Url::parse("https://files.example/a.txt").unwrap();
+        sign(&Default::default(), &mut url, 1000);
+        assert_eq!(url.query(), None);
+    }
+}
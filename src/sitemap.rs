@@ -0,0 +1,139 @@
+pub const PATH: &str = "sitemap.xml";
+
+const URLS_PER_SITEMAP: usize = 50_000;
+
+pub async fn handle(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    context: &worker::Context,
+    mount: &crate::mounts::Mount,
+) -> worker::Result<worker::Response> {
+    let url = request.url()?;
+    let origin = url.origin().ascii_serialization();
+    let page = url
+        .query_pairs()
+        .find(|(name, _)| name == "page")
+        .and_then(|(_, page)| page.parse::<usize>().ok());
+    let cache_key = |page: Option<usize>| match page {
+        Some(page) => format!("{origin}/{PATH}?page={page}"),
+        None => format!("{origin}/{PATH}"),
+    };
+    let ttl = config.sitemap_cache_ttl;
+    if ttl > 0 {
+        if let Some(response) = worker::Cache::default().get(cache_key(page), false).await? {
+            return Ok(response);
+        }
+    }
+
+    let host = url.host_str().unwrap_or_default();
+    let files = files(environment, config, host, mount).await?;
+    let documents = documents(&origin, &files);
+    let Some(sitemap) = documents
+        .iter()
+        .find(|(candidate, _)| *candidate == page)
+        .map(|(_, sitemap)| sitemap.clone())
+    else {
+        return crate::errors::response(environment, config, Some(mount), 404, "Not Found").await;
+    };
+    if ttl > 0 {
+        let cached = documents
+            .into_iter()
+            .map(|(page, sitemap)| {
+                let mut response = response(sitemap)?;
+                response
+                    .headers_mut()
+                    .set("cache-control", &format!("s-maxage={ttl}"))?;
+                Ok((cache_key(page), response))
+            })
+            .collect::<worker::Result<Vec<_>>>()?;
+        context.wait_until(async move {
+            let cache = worker::Cache::default();
+            for (key, response) in cached {
+                if let Err(err) = cache.put(key, response).await {
+                    worker::console_error!("failed to cache the sitemap: {err}");
+                }
+            }
+        });
+    }
+    response(sitemap)
+}
+
+fn response(sitemap: String) -> worker::Result<worker::Response> {
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "application/xml; charset=utf-8")?;
+    Ok(worker::Response::ok(sitemap)?.with_headers(headers))
+}
+
+async fn files(
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    host: &str,
+    mount: &crate::mounts::Mount,
+) -> worker::Result<Vec<(String, crate::store::Object)>> {
+    let filter = crate::filter::EntryFilter::from_config(config, false);
+    let mut files = crate::walk::files(mount, &mount.path, &filter).await?;
+    files.retain(|(path, _)| mount.serves(config, path));
+    for path in crate::mounts::paths(config) {
+        let Some(mounted) = crate::mounts::resolve(environment, config, host, &path)? else {
+            continue;
+        };
+        let mounted_files = crate::walk::files(&mounted, &mounted.path, &filter).await?;
+        files.extend(
+            mounted_files
+                .into_iter()
+                .filter(|(path, _)| mounted.serves(config, path)),
+        );
+    }
+    files.sort_by(|(left, _), (right, _)| left.cmp(right));
+    Ok(files)
+}
+
+fn documents(
+    origin: &str,
+    files: &[(String, crate::store::Object)],
+) -> Vec<(Option<usize>, String)> {
+    let pages = files.chunks(URLS_PER_SITEMAP).collect::<Vec<_>>();
+    let mut documents = Vec::new();
+    if pages.len() > 1 {
+        let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        sitemap.push_str("<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+        for (index, files) in pages.iter().enumerate() {
+            let lastmod = files
+                .iter()
+                .map(|(_, object)| object.uploaded)
+                .max()
+                .expect("chunks are never empty");
+            sitemap.push_str(&format!(
+                "<sitemap><loc>{}</loc><lastmod>{}</lastmod></sitemap>\n",
+                crate::escape_xml(&format!("{origin}/{PATH}?page={}", index + 1)),
+                lastmod.to_rfc3339(),
+            ));
+        }
+        sitemap.push_str("</sitemapindex>\n");
+        documents.push((None, sitemap));
+    } else {
+        documents.push((
+            None,
+            urlset(origin, pages.first().copied().unwrap_or_default()),
+        ));
+    }
+    for (index, files) in pages.iter().enumerate() {
+        documents.push((Some(index + 1), urlset(origin, files)));
+    }
+    documents
+}
+
+fn urlset(origin: &str, files: &[(String, crate::store::Object)]) -> String {
+    let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    sitemap.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for (path, object) in files {
+        sitemap.push_str(&format!(
+            "<url><loc>{}</loc><lastmod>{}</lastmod></url>\n",
+            crate::escape_xml(&format!("{origin}{}", crate::href(path))),
+            object.uploaded.to_rfc3339(),
+        ));
+    }
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
@@ -0,0 +1,35 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    Decimal,
+    Binary,
+}
+
+impl std::str::FromStr for Units {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "decimal" | "si" => Ok(Units::Decimal),
+            "binary" | "iec" => Ok(Units::Binary),
+            _ => Err(format!("unknown size units `{value}`")),
+        }
+    }
+}
+
+pub fn options(
+    config: &crate::config::Config,
+    url: Option<&url::Url>,
+) -> humansize::FormatSizeOptions {
+    let units = url
+        .and_then(|url| {
+            url.query_pairs()
+                .find(|(name, _)| name == "units")
+                .and_then(|(_, value)| value.parse().ok())
+        })
+        .unwrap_or(config.size_units);
+    humansize::FormatSizeOptions::from(match units {
+        Units::Decimal => humansize::DECIMAL,
+        Units::Binary => humansize::BINARY,
+    })
+    .decimal_places(config.size_decimal_places)
+}
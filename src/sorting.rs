@@ -0,0 +1,204 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::EntryType;
+
+#[derive(Clone, Copy)]
+pub struct Sorting {
+    natural: bool,
+    case_insensitive: bool,
+    directories_first: bool,
+    by_size: bool,
+}
+
+impl Sorting {
+    pub fn from_request(config: &crate::config::Config, url: Option<&url::Url>) -> Self {
+        let param = |param: &str, default: bool| {
+            url.and_then(|url| {
+                url.query_pairs()
+                    .find(|(name, _)| name == param)
+                    .map(|(_, value)| value != "0")
+            })
+            .unwrap_or(default)
+        };
+        Self {
+            natural: param("natural", config.sort_natural),
+            case_insensitive: param("ignore_case", config.sort_case_insensitive),
+            directories_first: param("dirs_first", config.sort_directories_first),
+            by_size: url
+                .and_then(|url| {
+                    url.query_pairs()
+                        .find(|(name, _)| name == "sort")
+                        .map(|(_, value)| value == "size")
+                })
+                .unwrap_or(config.sort_by_size),
+        }
+    }
+
+    pub fn in_key_order(&self) -> bool {
+        !(self.natural || self.case_insensitive || self.directories_first || self.by_size)
+    }
+
+    pub fn sort(&self, entries: &mut [(EntryType, String)]) {
+        entries.sort_by(|(left_type, left), (right_type, right)| {
+            let directories = match self.directories_first {
+                true => {
+                    (*left_type != EntryType::Directory).cmp(&(*right_type != EntryType::Directory))
+                }
+                false => Ordering::Equal,
+            };
+            let sizes = match self.by_size {
+                true => size(left_type).cmp(&size(right_type)),
+                false => Ordering::Equal,
+            };
+            directories
+                .then(sizes)
+                .then_with(|| self.compare(left, right))
+        });
+    }
+
+    fn compare(&self, left: &str, right: &str) -> Ordering {
+        let ordering = if self.natural {
+            natural(left, right, self.case_insensitive)
+        } else if self.case_insensitive {
+            left.chars()
+                .flat_map(char::to_lowercase)
+                .cmp(right.chars().flat_map(char::to_lowercase))
+        } else {
+            Ordering::Equal
+        };
+        ordering.then_with(|| left.cmp(right))
+    }
+}
+
+fn size(entry_type: &EntryType) -> Option<u64> {
+    match entry_type {
+        EntryType::File { size, .. } => Some(*size),
+        EntryType::Directory => None,
+    }
+}
+
+fn natural(left: &str, right: &str, case_insensitive: bool) -> Ordering {
+    let (mut left, mut right) = (left.chars().peekable(), right.chars().peekable());
+    loop {
+        let ordering = match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let (l, r) = (digits(&mut left), digits(&mut right));
+                let (l, r) = (l.trim_start_matches('0'), r.trim_start_matches('0'));
+                l.len().cmp(&r.len()).then_with(|| l.cmp(r))
+            }
+            (Some(l), Some(r)) => {
+                left.next();
+                right.next();
+                match case_insensitive {
+                    true => l.to_lowercase().cmp(r.to_lowercase()),
+                    false => l.cmp(&r),
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn file(name: &str, size: u64) -> (EntryType, String) {
+        let uploaded = chrono::DateTime::default();
+        (EntryType::File { size, uploaded }, name.to_owned())
+    }
+
+    fn by_size(directories_first: bool) -> Sorting {
+        Sorting {
+            natural: false,
+            case_insensitive: false,
+            directories_first,
+            by_size: true,
+        }
+    }
+
+    fn names(entries: &[(EntryType, String)]) -> Vec<&str> {
+        entries.iter().map(|(_, name)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_sizes_across_u32_max() {
+        let max = u64::from(u32::MAX);
+        let mut entries = vec![
+            file("a", 5 * GIB),
+            file("b", max + 1),
+            file("c", 1),
+            file("d", max),
+            file("e", max - 1),
+            file("f", 4 * GIB),
+        ];
+        by_size(true).sort(&mut entries);
+        assert_eq!(names(&entries), ["c", "e", "d", "b", "f", "a"]);
+    }
+
+    #[test]
+    fn does_not_wrap_sizes_over_4_gib() {
+        let mut entries = vec![file("large", 4 * GIB + 1), file("small", 2)];
+        by_size(true).sort(&mut entries);
+        assert_eq!(names(&entries), ["small", "large"]);
+    }
+
+    #[test]
+    fn breaks_size_ties_by_name() {
+        let mut entries = vec![file("b", 5 * GIB), file("a", 5 * GIB)];
+        by_size(true).sort(&mut entries);
+        assert_eq!(names(&entries), ["a", "b"]);
+    }
+
+    #[test]
+    fn keeps_directories_apart_from_sizes() {
+        let mut entries = vec![
+            file("large", 6 * GIB),
+            (EntryType::Directory, "directory/".to_owned()),
+            file("small", 6),
+        ];
+        by_size(true).sort(&mut entries);
+        assert_eq!(names(&entries), ["directory/", "small", "large"]);
+        entries.reverse();
+        by_size(false).sort(&mut entries);
+        assert_eq!(names(&entries), ["directory/", "small", "large"]);
+    }
+
+    #[test]
+    fn reads_the_size_order_from_the_query() {
+        let config = crate::config::Config::default();
+        let url = url::Url::parse("https://example.com/?sort=size").unwrap();
+        let mut entries = vec![file("a", u64::from(u32::MAX) + 2), file("b", 3)];
+        Sorting::from_request(&config, Some(&url)).sort(&mut entries);
+        assert_eq!(names(&entries), ["b", "a"]);
+        Sorting::from_request(&config, None).sort(&mut entries);
+        assert_eq!(names(&entries), ["a", "b"]);
+    }
+
+    #[test]
+    fn pages_only_in_key_order() {
+        let config = crate::config::Config::from_vars(&[("SORT_DIRECTORIES_FIRST", "false")]);
+        assert!(Sorting::from_request(&config, None).in_key_order());
+        let url = url::Url::parse("https://example.com/?natural=1").unwrap();
+        assert!(!Sorting::from_request(&config, Some(&url)).in_key_order());
+        let config = crate::config::Config::default();
+        assert!(!Sorting::from_request(&config, None).in_key_order());
+    }
+}
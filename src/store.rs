@@ -0,0 +1,423 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use base64::Engine;
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use itertools::Itertools;
+use sha2::Digest;
+use worker::worker_sys::web_sys;
+
+const DEFAULT_LIST_LIMIT: u32 = 1000;
+
+pub const CHECKSUM_ALGORITHMS: [&str; 5] = ["md5", "sha1", "sha256", "sha384", "sha512"];
+
+#[derive(Clone, Default)]
+pub struct ListOptions {
+    pub prefix: String,
+    pub delimiter: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+    pub include_metadata: bool,
+}
+
+#[derive(Default)]
+pub struct Listing {
+    pub objects: Vec<Object>,
+    pub delimited_prefixes: Vec<String>,
+    pub cursor: Option<String>,
+}
+
+impl Listing {
+    pub fn truncated(&self) -> bool {
+        self.cursor.is_some()
+    }
+}
+
+#[derive(Clone)]
+pub struct Object {
+    pub key: String,
+    pub size: u64,
+    pub uploaded: chrono::DateTime<chrono::Utc>,
+    pub http_metadata: Vec<(String, String)>,
+    pub etag: String,
+    pub checksums: Vec<(String, String)>,
+    pub custom_metadata: Vec<(String, String)>,
+}
+
+impl Object {
+    pub fn write_http_metadata(&self, headers: &mut worker::Headers) -> worker::Result<()> {
+        for (name, value) in &self.http_metadata {
+            headers.set(name, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_integrity_headers(&self, headers: &mut worker::Headers) -> worker::Result<()> {
+        if !self.etag.is_empty() {
+            headers.set("etag", &self.etag)?;
+        }
+        let digests = self
+            .checksums
+            .iter()
+            .filter_map(|(algorithm, checksum)| {
+                let name = match algorithm.as_str() {
+                    "md5" => "md5",
+                    "sha1" => "sha",
+                    "sha256" => "sha-256",
+                    "sha512" => "sha-512",
+                    _ => return None,
+                };
+                let checksum = crate::unhex(checksum)?;
+                Some(format!(
+                    "{name}=:{}:",
+                    base64::engine::general_purpose::STANDARD.encode(checksum)
+                ))
+            })
+            .join(", ");
+        if !digests.is_empty() {
+            headers.set("repr-digest", &digests)?;
+        }
+        Ok(())
+    }
+}
+
+pub enum Body {
+    Stream(web_sys::ReadableStream),
+    Bytes(Vec<u8>),
+}
+
+impl Body {
+    pub fn into_response(self) -> worker::Result<worker::Response> {
+        match self {
+            Body::Stream(stream) => {
+                Ok(web_sys::Response::new_with_opt_readable_stream(Some(&stream))?.into())
+            }
+            Body::Bytes(bytes) => worker::Response::from_bytes(bytes),
+        }
+    }
+
+    pub async fn text(self) -> worker::Result<String> {
+        self.into_response()?.text().await
+    }
+}
+
+pub trait ObjectStore {
+    fn list(&self, options: ListOptions) -> LocalBoxFuture<'_, worker::Result<Listing>>;
+
+    fn head<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, worker::Result<Option<Object>>>;
+
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> LocalBoxFuture<'a, worker::Result<Option<(Object, Body)>>>;
+
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        offset: u64,
+        length: u64,
+    ) -> LocalBoxFuture<'a, worker::Result<Option<(Object, Body)>>>;
+
+    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> LocalBoxFuture<'a, worker::Result<()>>;
+
+    fn delete<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, worker::Result<()>>;
+
+    fn rename<'a>(&'a self, from: &'a str, to: &'a str)
+        -> LocalBoxFuture<'a, worker::Result<bool>>;
+}
+
+pub fn binding(environment: &worker::Env, name: &str) -> worker::Result<Rc<dyn ObjectStore>> {
+    Ok(Rc::new(environment.bucket(name)?))
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    objects: RefCell<BTreeMap<String, (Object, Vec<u8>)>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, object: Object, value: Vec<u8>) {
+        self.objects
+            .borrow_mut()
+            .insert(object.key.clone(), (object, value));
+    }
+}
+
+impl ObjectStore for MemoryStore {
+    fn list(&self, options: ListOptions) -> LocalBoxFuture<'_, worker::Result<Listing>> {
+        let objects = self.objects.borrow();
+        let limit = options.limit.unwrap_or(DEFAULT_LIST_LIMIT).max(1) as usize;
+        let mut listing = Listing::default();
+        let mut last = None;
+        for (key, (object, _)) in objects.iter() {
+            if !key.starts_with(&options.prefix) {
+                continue;
+            }
+            if let Some(cursor) = &options.cursor {
+                let skipped_prefix = options
+                    .delimiter
+                    .as_deref()
+                    .is_some_and(|delimiter| cursor.ends_with(delimiter))
+                    && key.starts_with(cursor.as_str());
+                if key <= cursor || skipped_prefix {
+                    continue;
+                }
+            }
+            let remainder = &key[options.prefix.len()..];
+            let delimited = options.delimiter.as_deref().and_then(|delimiter| {
+                remainder
+                    .find(delimiter)
+                    .map(|index| key[..options.prefix.len() + index + delimiter.len()].to_owned())
+            });
+            if delimited.is_some() && delimited == listing.delimited_prefixes.last().cloned() {
+                continue;
+            }
+            if listing.objects.len() + listing.delimited_prefixes.len() == limit {
+                listing.cursor = last;
+                break;
+            }
+            match delimited {
+                Some(prefix) => {
+                    last = Some(prefix.clone());
+                    listing.delimited_prefixes.push(prefix);
+                }
+                None => {
+                    last = Some(key.clone());
+                    listing.objects.push(object.clone());
+                }
+            }
+        }
+        std::future::ready(Ok(listing)).boxed_local()
+    }
+
+    fn head<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, worker::Result<Option<Object>>> {
+        let object = self
+            .objects
+            .borrow()
+            .get(key)
+            .map(|(object, _)| object.clone());
+        std::future::ready(Ok(object)).boxed_local()
+    }
+
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> LocalBoxFuture<'a, worker::Result<Option<(Object, Body)>>> {
+        let object = self
+            .objects
+            .borrow()
+            .get(key)
+            .map(|(object, value)| (object.clone(), Body::Bytes(value.clone())));
+        std::future::ready(Ok(object)).boxed_local()
+    }
+
+    fn get_range<'a>(
+        &'a self,
+        key: &'a str,
+        offset: u64,
+        length: u64,
+    ) -> LocalBoxFuture<'a, worker::Result<Option<(Object, Body)>>> {
+        let object = self.objects.borrow().get(key).map(|(object, value)| {
+            let start = (offset as usize).min(value.len());
+            let end = start.saturating_add(length as usize).min(value.len());
+            (object.clone(), Body::Bytes(value[start..end].to_vec()))
+        });
+        std::future::ready(Ok(object)).boxed_local()
+    }
+
+    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> LocalBoxFuture<'a, worker::Result<()>> {
+        let checksum = crate::hex(&sha2::Sha256::digest(&value));
+        self.insert(
+            Object {
+                key: key.to_owned(),
+                size: value.len() as u64,
+                uploaded: chrono::Utc::now(),
+                http_metadata: Vec::new(),
+                etag: format!("\"{}\"", &checksum[..32]),
+                checksums: vec![("sha256".into(), checksum)],
+                custom_metadata: Vec::new(),
+            },
+            value,
+        );
+        std::future::ready(Ok(())).boxed_local()
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> LocalBoxFuture<'a, worker::Result<()>> {
+        self.objects.borrow_mut().remove(key);
+        std::future::ready(Ok(())).boxed_local()
+    }
+
+    fn rename<'a>(
+        &'a self,
+        from: &'a str,
+        to: &'a str,
+    ) -> LocalBoxFuture<'a, worker::Result<bool>> {
+        let mut objects = self.objects.borrow_mut();
+        let renamed = match objects.remove(from) {
+            Some((object, value)) => {
+                let object = Object {
+                    key: to.to_owned(),
+                    uploaded: chrono::Utc::now(),
+                    ..object
+                };
+                objects.insert(to.to_owned(), (object, value));
+                true
+            }
+            None => false,
+        };
+        std::future::ready(Ok(renamed)).boxed_local()
+    }
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    pub fn with_files(files: &[(&str, &str)]) -> Self {
+        let store = Self::new();
+        for (key, contents) in files {
+            store
+                .put(key, contents.as_bytes().to_vec())
+                .now_or_never()
+                .expect("the memory store must not block")
+                .expect("the memory store must not fail");
+        }
+        store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<T>(future: LocalBoxFuture<'_, worker::Result<T>>) -> T {
+        future.now_or_never().unwrap().unwrap()
+    }
+
+    fn keys(listing: &Listing) -> Vec<&str> {
+        listing
+            .delimited_prefixes
+            .iter()
+            .map(String::as_str)
+            .chain(listing.objects.iter().map(|object| object.key.as_str()))
+            .collect()
+    }
+
+    fn bytes(body: Body) -> Vec<u8> {
+        match body {
+            Body::Bytes(bytes) => bytes,
+            Body::Stream(_) => panic!("the memory store must return bytes"),
+        }
+    }
+
+    #[test]
+    fn groups_keys_below_the_delimiter() {
+        let store = MemoryStore::with_files(&[
+            ("a.txt", "a"),
+            ("docs/b.txt", "b"),
+            ("docs/deep/c.txt", "c"),
+            ("pics/d.png", "d"),
+        ]);
+        let listing = run(store.list(ListOptions {
+            delimiter: Some("/".into()),
+            ..Default::default()
+        }));
+        assert_eq!(keys(&listing), ["docs/", "pics/", "a.txt"]);
+        assert!(!listing.truncated());
+
+        let listing = run(store.list(ListOptions {
+            prefix: "docs/".into(),
+            delimiter: Some("/".into()),
+            ..Default::default()
+        }));
+        assert_eq!(keys(&listing), ["docs/deep/", "docs/b.txt"]);
+    }
+
+    #[test]
+    fn pages_with_cursors() {
+        let store = MemoryStore::with_files(&[
+            ("a", ""),
+            ("b/1", ""),
+            ("b/2", ""),
+            ("b/3", ""),
+            ("c", ""),
+            ("d", ""),
+        ]);
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let listing = run(store.list(ListOptions {
+                delimiter: Some("/".into()),
+                cursor: cursor.take(),
+                limit: Some(2),
+                ..Default::default()
+            }));
+            seen.extend(keys(&listing).into_iter().map(str::to_owned));
+            match listing.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, ["a", "b/", "c", "d"]);
+    }
+
+    #[test]
+    fn lists_without_a_delimiter_recursively() {
+        let store = MemoryStore::with_files(&[("a/b/c", ""), ("a/d", ""), ("e", "")]);
+        let listing = run(store.list(ListOptions {
+            prefix: "a/".into(),
+            ..Default::default()
+        }));
+        assert_eq!(keys(&listing), ["a/b/c", "a/d"]);
+    }
+
+    #[test]
+    fn reads_ranges() {
+        let store = MemoryStore::with_files(&[("file", "0123456789")]);
+        let (object, body) = run(store.get_range("file", 2, 3)).unwrap();
+        assert_eq!(object.size, 10);
+        assert_eq!(bytes(body), b"234");
+        let (_, body) = run(store.get_range("file", 8, 100)).unwrap();
+        assert_eq!(bytes(body), b"89");
+        let (_, body) = run(store.get_range("file", 20, 1)).unwrap();
+        assert!(bytes(body).is_empty());
+        assert!(run(store.get_range("missing", 0, 1)).is_none());
+    }
+
+    #[test]
+    fn puts_heads_and_deletes() {
+        let store = MemoryStore::new();
+        run(store.put("file", b"abc".to_vec()));
+        let object = run(store.head("file")).unwrap();
+        assert_eq!(object.size, 3);
+        assert_eq!(
+            object.checksums,
+            [(
+                "sha256".to_owned(),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_owned()
+            )]
+        );
+        assert_eq!(object.etag, "\"ba7816bf8f01cfea414140de5dae2223\"");
+        let (_, body) = run(store.get("file")).unwrap();
+        assert_eq!(bytes(body), b"abc");
+        run(store.delete("file"));
+        assert!(run(store.head("file")).is_none());
+        assert!(run(store.get("file")).is_none());
+    }
+
+    #[test]
+    fn renames() {
+        let store = MemoryStore::with_files(&[("file", "abc")]);
+        assert!(run(store.rename("file", "moved/file")));
+        assert!(run(store.head("file")).is_none());
+        let (object, body) = run(store.get("moved/file")).unwrap();
+        assert_eq!(object.key, "moved/file");
+        assert_eq!(bytes(body), b"abc");
+        assert!(!run(store.rename("file", "again")));
+        assert!(run(store.head("again")).is_none());
+    }
+}
@@ -0,0 +1,51 @@
+use futures_util::StreamExt;
+
+const CHUNKS_PER_SECOND: u64 = 10;
+
+pub fn apply(
+    request: &worker::Request,
+    config: &crate::config::Config,
+    response: worker::Response,
+) -> worker::Result<worker::Response> {
+    let Some(rate) = config.throttle_bytes_per_sec else {
+        return Ok(response);
+    };
+    if config.throttle_anonymous_only && crate::auth::identity(request, config)?.is_some() {
+        return Ok(response);
+    }
+    let mut response =
+        worker::Response::from(worker::worker_sys::web_sys::Response::from(response));
+    let status = response.status_code();
+    let headers = response.headers().clone();
+    let chunk_size = (rate / CHUNKS_PER_SECOND).max(1) as usize;
+    let started = worker::Date::now().as_millis();
+
+    let Ok(stream) = response.stream() else {
+        return Ok(response);
+    };
+    let chunks = stream.flat_map(move |chunk| {
+        futures_util::stream::iter(match chunk {
+            Ok(chunk) => chunk
+                .chunks(chunk_size)
+                .map(|piece| Ok(piece.to_vec()))
+                .collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        })
+    });
+    let paced = chunks.scan(0u64, move |sent, chunk| {
+        let due = started + *sent * 1000 / rate;
+        if let Ok(chunk) = &chunk {
+            *sent += chunk.len() as u64;
+        }
+        async move {
+            let wait = due.saturating_sub(worker::Date::now().as_millis());
+            if wait > 0 {
+                worker::Delay::from(std::time::Duration::from_millis(wait)).await;
+            }
+            Some(chunk)
+        }
+    });
+    Ok(worker::Response::from_stream(paced)?
+        .with_status(status)
+        .with_headers(headers))
+}
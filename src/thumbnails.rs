@@ -0,0 +1,19 @@
+const IMAGE_EXTENSIONS: [&str; 8] = ["avif", "bmp", "gif", "jpeg", "jpg", "png", "svg", "webp"];
+
+pub fn is_image(config: &crate::config::Config, key: &str) -> bool {
+    crate::extension(key).is_some_and(|extension| match config.mime_types.get(&extension) {
+        Some(content_type) => content_type.starts_with("image/"),
+        None => IMAGE_EXTENSIONS.contains(&extension.as_str()),
+    })
+}
+
+pub fn url(config: &crate::config::Config, path: &str) -> String {
+    let href = crate::href(path);
+    if !config.thumbnails || crate::extension(path).as_deref() == Some("svg") {
+        return href;
+    }
+    format!(
+        "/cdn-cgi/image/width={},height={},fit=cover{href}",
+        config.thumbnail_width, config.thumbnail_height
+    )
+}
@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+
+use futures_util::StreamExt;
+use sha1::Digest;
+
+const BINDING: &str = "TORRENTS";
+const KEY_PREFIX: &str = "torrent:";
+const TARGET_PIECES: u64 = 1500;
+const MIN_PIECE_LENGTH: u64 = 256 * 1024;
+const MAX_PIECE_LENGTH: u64 = 16 * 1024 * 1024;
+
+enum Value {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dictionary(BTreeMap<&'static str, Value>),
+}
+
+impl Value {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Integer(integer) => out.extend(format!("i{integer}e").into_bytes()),
+            Value::Bytes(bytes) => {
+                out.extend(format!("{}:", bytes.len()).into_bytes());
+                out.extend(bytes);
+            }
+            Value::List(values) => {
+                out.push(b'l');
+                for value in values {
+                    value.encode(out);
+                }
+                out.push(b'e');
+            }
+            Value::Dictionary(entries) => {
+                out.push(b'd');
+                for (key, value) in entries {
+                    Value::from(*key).encode(out);
+                    value.encode(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Bytes(text.as_bytes().to_vec())
+    }
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+    key: &str,
+) -> worker::Result<Option<worker::Response>> {
+    let store = mount.store.as_ref();
+    let url = request.url()?;
+    if !url.query_pairs().any(|(name, _)| name == "torrent") {
+        return Ok(None);
+    }
+    let Some(object) = store.head(key).await? else {
+        return Ok(None);
+    };
+    let piece_length = (object.size / TARGET_PIECES)
+        .next_power_of_two()
+        .clamp(MIN_PIECE_LENGTH, MAX_PIECE_LENGTH);
+    let cache = environment.kv(BINDING).ok();
+    let cache_key = format!("{KEY_PREFIX}{}:{piece_length}", object.etag);
+    let cached = match &cache {
+        Some(cache) => cache.get(&cache_key).bytes().await?,
+        None => None,
+    };
+    let pieces = match cached {
+        Some(pieces) => pieces,
+        None if config.torrent_max_size > 0 && object.size > config.torrent_max_size => {
+            return crate::errors::response(
+                environment,
+                config,
+                Some(mount),
+                413,
+                "Payload Too Large",
+            )
+            .await
+            .map(Some);
+        }
+        None => {
+            let Some(pieces) = pieces(store, &object, piece_length).await? else {
+                return Ok(None);
+            };
+            if let Some(cache) = &cache {
+                cache.put_bytes(&cache_key, &pieces)?.execute().await?;
+            }
+            pieces
+        }
+    };
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let web_seed = format!(
+        "{}{}",
+        url.origin().ascii_serialization(),
+        crate::href(path)
+    );
+    let body = metainfo(config, &object, name, &web_seed, piece_length, pieces);
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "application/x-bittorrent")?;
+    headers.set(
+        "content-disposition",
+        &crate::content_disposition::header("attachment", &format!("{name}.torrent")),
+    )?;
+    Ok(Some(
+        worker::Response::from_bytes(body)?.with_headers(headers),
+    ))
+}
+
+fn metainfo(
+    config: &crate::config::Config,
+    object: &crate::store::Object,
+    name: &str,
+    web_seed: &str,
+    piece_length: u64,
+    pieces: Vec<u8>,
+) -> Vec<u8> {
+    let info = BTreeMap::from([
+        ("length", Value::Integer(object.size as i64)),
+        ("name", name.into()),
+        ("piece length", Value::Integer(piece_length as i64)),
+        ("pieces", Value::Bytes(pieces)),
+    ]);
+    let mut torrent = BTreeMap::from([
+        (
+            "created by",
+            concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")).into(),
+        ),
+        ("creation date", Value::Integer(object.uploaded.timestamp())),
+        ("info", Value::Dictionary(info)),
+        ("url-list", Value::List(vec![web_seed.into()])),
+    ]);
+    if let Some(tracker) = config.torrent_trackers.first() {
+        torrent.insert("announce", tracker.as_str().into());
+    }
+    if config.torrent_trackers.len() > 1 {
+        torrent.insert(
+            "announce-list",
+            Value::List(
+                config
+                    .torrent_trackers
+                    .iter()
+                    .map(|tracker| Value::List(vec![tracker.as_str().into()]))
+                    .collect(),
+            ),
+        );
+    }
+    let mut body = Vec::new();
+    Value::Dictionary(torrent).encode(&mut body);
+    body
+}
+
+async fn pieces(
+    store: &dyn crate::store::ObjectStore,
+    object: &crate::store::Object,
+    piece_length: u64,
+) -> worker::Result<Option<Vec<u8>>> {
+    let Some((_, body)) = store.get(&object.key).await? else {
+        return Ok(None);
+    };
+    let piece_length = piece_length as usize;
+    let mut pieces = Vec::new();
+    match body {
+        crate::store::Body::Bytes(bytes) => {
+            for piece in bytes.chunks(piece_length) {
+                pieces.extend(sha1::Sha1::digest(piece));
+            }
+        }
+        body => {
+            let mut piece = Vec::with_capacity(piece_length);
+            let mut stream = body.into_response()?.stream()?;
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                let mut rest = chunk.as_slice();
+                while !rest.is_empty() {
+                    let (head, tail) = rest.split_at(rest.len().min(piece_length - piece.len()));
+                    piece.extend_from_slice(head);
+                    rest = tail;
+                    if piece.len() == piece_length {
+                        pieces.extend(sha1::Sha1::digest(&piece));
+                        piece.clear();
+                    }
+                }
+            }
+            if !piece.is_empty() {
+                pieces.extend(sha1::Sha1::digest(&piece));
+            }
+        }
+    }
+    Ok(Some(pieces))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+    use crate::store::ObjectStore;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn encodes_bencode() {
+        let mut out = Vec::new();
+        Value::Dictionary(BTreeMap::from([
+            ("spam", Value::List(vec!["a".into(), Value::Integer(-3)])),
+            ("cow", "moo".into()),
+            ("empty", Value::Bytes(Vec::new())),
+        ]))
+        .encode(&mut out);
+        assert_eq!(out, b"d3:cow3:moo5:empty0:4:spaml1:ai-3eee");
+    }
+
+    #[test]
+    fn builds_a_known_torrent() {
+        let store = crate::store::MemoryStore::with_files(&[("docs/a.txt", "abc")]);
+        let mut object = store
+            .head("docs/a.txt")
+            .now_or_never()
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        object.uploaded = "2024-01-01T00:00:00Z".parse().unwrap();
+        let pieces = pieces(&store, &object, MIN_PIECE_LENGTH)
+            .now_or_never()
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(pieces, sha1::Sha1::digest(b"abc").to_vec());
+
+        let config = crate::config::Config::from_vars(&[(
+            "TORRENT_TRACKERS",
+            "udp://tracker.example:1337,https://tracker.example/announce",
+        )]);
+        let torrent = metainfo(
+            &config,
+            &object,
+            "a.txt",
+            "https://example.com/docs/a.txt",
+            MIN_PIECE_LENGTH,
+            pieces,
+        );
+        let mut info = b"d6:lengthi3e4:name5:a.txt12:piece lengthi262144e6:pieces20:".to_vec();
+        info.extend(sha1::Sha1::digest(b"abc"));
+        info.push(b'e');
+        assert_eq!(
+            hex(&sha1::Sha1::digest(&info)),
+            "c80240f933df10f514f9b1604bb657e7bb3c52bf"
+        );
+
+        let created_by = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+        let mut expected = format!(
+            "d8:announce26:udp://tracker.example:133713:announce-listll26:udp://tracker.example:1337el32:https://tracker.example/announceee10:created by{}:{created_by}13:creation datei1704067200e4:info",
+            created_by.len()
+        )
+        .into_bytes();
+        expected.extend(&info);
+        expected.extend(b"8:url-listl30:https://example.com/docs/a.txtee");
+        assert_eq!(torrent, expected);
+    }
+}
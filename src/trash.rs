@@ -0,0 +1,360 @@
+use chrono::{DateTime, Utc};
+
+pub const PATH: &str = "/_trash";
+
+const TIMESTAMP: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+struct Item {
+    object: crate::store::Object,
+    deleted: DateTime<Utc>,
+    path: String,
+}
+
+pub fn contains(config: &crate::config::Config, key: &str) -> bool {
+    config
+        .trash_prefix
+        .as_deref()
+        .is_some_and(|prefix| key.starts_with(prefix))
+}
+
+pub async fn delete(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+    mount: &crate::mounts::Mount,
+    path: &str,
+) -> worker::Result<worker::Response> {
+    if crate::auth::identity(request, config)?.is_none() {
+        return crate::auth::challenge();
+    }
+    if cross_site(request)? {
+        return crate::errors::response(environment, config, Some(mount), 403, "Forbidden").await;
+    }
+    let key = mount.key(path);
+    let store = mount.store.as_ref();
+    let deleted = match &config.trash_prefix {
+        Some(prefix) => discard(store, prefix, &key, Utc::now()).await?,
+        None => match store.head(&key).await? {
+            Some(_) => {
+                store.delete(&key).await?;
+                true
+            }
+            None => false,
+        },
+    };
+    if !deleted {
+        return crate::errors::response(environment, config, Some(mount), 404, "Not Found").await;
+    }
+    Ok(worker::Response::empty()?.with_status(204))
+}
+
+pub async fn handle(
+    request: &worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<worker::Response> {
+    if crate::auth::identity(request, config)?.is_none() {
+        return crate::auth::challenge();
+    }
+    let Some(prefix) = config.trash_prefix.as_deref() else {
+        return crate::errors::for_request(request, environment, config, 404, "Not Found").await;
+    };
+    let url = request.url()?;
+    let parameter = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let mut headers = worker::Headers::new();
+    headers.set("cache-control", "no-store")?;
+
+    if request.method() == worker::Method::Get {
+        let mut trashed = Vec::new();
+        for binding in crate::mounts::bindings(config) {
+            let store = crate::store::binding(environment, binding)?;
+            trashed.extend(
+                items(store.as_ref(), prefix)
+                    .await?
+                    .into_iter()
+                    .map(|item| {
+                        serde_json::json!({
+                            "bucket": binding,
+                            "key": item.object.key,
+                            "path": item.path,
+                            "deleted": item.deleted.to_rfc3339(),
+                            "size": item.object.size,
+                        })
+                    }),
+            );
+        }
+        return Ok(worker::Response::from_json(&trashed)?.with_headers(headers));
+    }
+
+    if cross_site(request)? {
+        return crate::errors::for_request(request, environment, config, 403, "Forbidden").await;
+    }
+    let restore = parameter("restore");
+    let purge = parameter("purge");
+    if restore.is_none() && purge.is_none() {
+        return crate::errors::for_request(
+            request,
+            environment,
+            config,
+            400,
+            "expected `restore` or `purge`",
+        )
+        .await;
+    }
+    if restore.is_none() && purge.as_deref() == Some("") {
+        let before = Utc::now() - chrono::Duration::seconds(config.trash_retention as i64);
+        let mut purged = 0;
+        for binding in crate::mounts::bindings(config) {
+            let store = crate::store::binding(environment, binding)?;
+            purged += expire(store.as_ref(), prefix, before).await?;
+        }
+        return Ok(
+            worker::Response::from_json(&serde_json::json!({ "purged": purged }))?
+                .with_headers(headers),
+        );
+    }
+    let Some(binding) = parameter("bucket")
+        .filter(|bucket| crate::mounts::bindings(config).contains(bucket.as_str()))
+    else {
+        return crate::errors::for_request(
+            request,
+            environment,
+            config,
+            400,
+            "missing or unknown `bucket`",
+        )
+        .await;
+    };
+    let store = crate::store::binding(environment, &binding)?;
+    let status = match (restore, purge) {
+        (Some(key), _) => recover(store.as_ref(), prefix, &key).await?,
+        (None, Some(key)) if key.starts_with(prefix) && store.head(&key).await?.is_some() => {
+            store.delete(&key).await?;
+            204
+        }
+        _ => 404,
+    };
+    match status {
+        204 => Ok(worker::Response::empty()?
+            .with_status(204)
+            .with_headers(headers)),
+        409 => {
+            crate::errors::for_request(
+                request,
+                environment,
+                config,
+                409,
+                "a file already exists at the original path",
+            )
+            .await
+        }
+        status => {
+            crate::errors::for_request(request, environment, config, status, "Not Found").await
+        }
+    }
+}
+
+pub async fn purge(environment: &worker::Env) -> worker::Result<()> {
+    let config = crate::config::Config::load(environment).await?;
+    let Some(prefix) = config.trash_prefix.as_deref() else {
+        return Ok(());
+    };
+    let before = Utc::now() - chrono::Duration::seconds(config.trash_retention as i64);
+    for binding in crate::mounts::bindings(&config) {
+        let store = crate::store::binding(environment, binding)?;
+        let purged = expire(store.as_ref(), prefix, before).await?;
+        if purged > 0 {
+            worker::console_log!("purged {purged} trashed files from {binding}");
+        }
+    }
+    Ok(())
+}
+
+/// Whether a browser sent the request from another site, so a page there
+/// can't have a signed-in visitor delete or restore files. Clients that send
+/// neither header aren't browsers and are let through.
+fn cross_site(request: &worker::Request) -> worker::Result<bool> {
+    let headers = request.headers();
+    let origin = request.url()?.origin().ascii_serialization();
+    Ok(forged(
+        headers.get("sec-fetch-site")?.as_deref(),
+        headers.get("origin")?.as_deref(),
+        &origin,
+    ))
+}
+
+fn forged(fetch_site: Option<&str>, origin: Option<&str>, expected: &str) -> bool {
+    match fetch_site {
+        Some(site) => !matches!(site, "same-origin" | "none"),
+        None => origin.is_some_and(|origin| origin != expected),
+    }
+}
+
+async fn discard(
+    store: &dyn crate::store::ObjectStore,
+    prefix: &str,
+    key: &str,
+    now: DateTime<Utc>,
+) -> worker::Result<bool> {
+    let trashed = format!("{prefix}{}/{key}", now.format(TIMESTAMP));
+    store.rename(key, &trashed).await
+}
+
+async fn recover(
+    store: &dyn crate::store::ObjectStore,
+    prefix: &str,
+    key: &str,
+) -> worker::Result<u16> {
+    let Some((_, path)) = original(prefix, key) else {
+        return Ok(404);
+    };
+    if store.head(key).await?.is_none() {
+        return Ok(404);
+    }
+    if store.head(path).await?.is_some() {
+        return Ok(409);
+    }
+    Ok(match store.rename(key, path).await? {
+        true => 204,
+        false => 404,
+    })
+}
+
+async fn expire(
+    store: &dyn crate::store::ObjectStore,
+    prefix: &str,
+    before: DateTime<Utc>,
+) -> worker::Result<usize> {
+    let mut purged = 0;
+    for item in items(store, prefix).await? {
+        if item.deleted < before {
+            store.delete(&item.object.key).await?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+async fn items(store: &dyn crate::store::ObjectStore, prefix: &str) -> worker::Result<Vec<Item>> {
+    let mut items = Vec::new();
+    let mut cursor = None;
+    loop {
+        let listing = store
+            .list(crate::store::ListOptions {
+                prefix: prefix.to_owned(),
+                cursor,
+                ..Default::default()
+            })
+            .await?;
+        items.extend(listing.objects.into_iter().filter_map(|object| {
+            let (deleted, path) = original(prefix, &object.key)?;
+            let path = path.to_owned();
+            Some(Item {
+                object,
+                deleted,
+                path,
+            })
+        }));
+        match listing.cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(items),
+        }
+    }
+}
+
+fn original<'a>(prefix: &str, key: &'a str) -> Option<(DateTime<Utc>, &'a str)> {
+    let (deleted, path) = key.strip_prefix(prefix)?.split_once('/')?;
+    let deleted = DateTime::parse_from_rfc3339(deleted).ok()?;
+    (!path.is_empty()).then(|| (deleted.with_timezone(&Utc), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+    use crate::store::ObjectStore;
+
+    fn run<T>(future: impl std::future::Future<Output = worker::Result<T>>) -> T {
+        future.now_or_never().unwrap().unwrap()
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        timestamp.parse().unwrap()
+    }
+
+    #[test]
+    fn moves_files_into_the_trash_and_back() {
+        let store = crate::store::MemoryStore::with_files(&[("docs/a.txt", "abc")]);
+        let now = at("2024-01-02T03:04:05.678Z");
+        assert!(run(discard(&store, ".trash/", "docs/a.txt", now)));
+        assert!(!run(discard(&store, ".trash/", "docs/a.txt", now)));
+        assert!(run(store.head("docs/a.txt")).is_none());
+
+        let items = run(items(&store, ".trash/"));
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].object.key,
+            ".trash/2024-01-02T03:04:05.678Z/docs/a.txt"
+        );
+        assert_eq!(items[0].path, "docs/a.txt");
+        assert_eq!(items[0].deleted, now);
+
+        let key = items[0].object.key.clone();
+        run(store.put("docs/a.txt", b"new".to_vec()));
+        assert_eq!(run(recover(&store, ".trash/", &key)), 409);
+        run(store.delete("docs/a.txt"));
+        assert_eq!(run(recover(&store, ".trash/", &key)), 204);
+        assert_eq!(run(store.head("docs/a.txt")).unwrap().size, 3);
+        assert_eq!(run(recover(&store, ".trash/", &key)), 404);
+        assert_eq!(run(recover(&store, ".trash/", "docs/a.txt")), 404);
+    }
+
+    #[test]
+    fn purges_files_past_retention() {
+        let store = crate::store::MemoryStore::with_files(&[
+            (".trash/2024-01-01T00:00:00.000Z/old.txt", "old"),
+            (".trash/2024-03-01T00:00:00.000Z/new.txt", "new"),
+            (".trash/not-a-timestamp/kept.txt", "kept"),
+        ]);
+        assert_eq!(
+            run(expire(&store, ".trash/", at("2024-02-01T00:00:00Z"))),
+            1
+        );
+        assert!(run(store.head(".trash/2024-01-01T00:00:00.000Z/old.txt")).is_none());
+        assert!(run(store.head(".trash/2024-03-01T00:00:00.000Z/new.txt")).is_some());
+        assert!(run(store.head(".trash/not-a-timestamp/kept.txt")).is_some());
+    }
+
+    #[test]
+    fn rejects_cross_site_requests() {
+        let origin = "https://files.example";
+        assert!(!forged(None, None, origin));
+        assert!(!forged(Some("same-origin"), Some(origin), origin));
+        assert!(!forged(Some("none"), None, origin));
+        assert!(!forged(None, Some(origin), origin));
+        assert!(forged(Some("cross-site"), None, origin));
+        assert!(forged(
+            Some("same-site"),
+            Some("https://other.example"),
+            origin
+        ));
+        assert!(forged(None, Some("https://evil.example"), origin));
+        assert!(forged(None, Some("null"), origin));
+    }
+
+    #[test]
+    fn recognises_trashed_keys() {
+        let config = crate::config::Config::from_vars(&[("TRASH_PREFIX", "/.trash")]);
+        assert_eq!(config.trash_prefix.as_deref(), Some(".trash/"));
+        assert!(contains(&config, ".trash/2024-01-01T00:00:00.000Z/a.txt"));
+        assert!(contains(&config, ".trash/"));
+        assert!(!contains(&config, ".trash"));
+        assert!(!contains(&config, "docs/.trash/a.txt"));
+        assert!(!contains(&crate::config::Config::default(), ".trash/a.txt"));
+    }
+}
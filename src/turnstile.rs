@@ -0,0 +1,203 @@
+use hmac::Mac;
+
+const COOKIE_NAME: &str = "turnstile_pass";
+const SITEVERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+markup::define! {
+    Challenge<'a>(site_key: &'a str, font_url: Option<&'a str>) {
+        @markup::doctype()
+        html {
+            head {
+                meta[charset = "utf-8"] {}
+                meta[name = "viewport", content = "width=device-width, initial-scale=1"] {}
+                title { "Checking your browser…" }
+                script[src = "https://challenges.cloudflare.com/turnstile/v0/api.js", async, defer] {}
+                style {
+                    @crate::fonts::face(*font_url)
+                    "html { font-family: 'Inconsolata', monospace; }"
+                    "body { padding: 1em; }"
+                    "* { margin: 0; padding: 0; }"
+                    "header { margin-bottom: 2em; }"
+                }
+            }
+            body {
+                header {
+                    h1 { "Checking your browser…" }
+                }
+                form[id = "challenge", method = "post"] {
+                    div[
+                        class = "cf-turnstile",
+                        "data-sitekey" = site_key,
+                        "data-callback" = "onTurnstileSuccess"
+                    ] {}
+                    noscript {
+                        button[type = "submit"] { "Continue" }
+                    }
+                }
+                script {
+                    @markup::raw("function onTurnstileSuccess() { document.getElementById('challenge').submit(); }")
+                }
+            }
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SiteverifyResponse {
+    success: bool,
+}
+
+struct Settings<'a> {
+    site_key: &'a str,
+    secret_key: &'a str,
+    pass_ttl: u64,
+    font_url: Option<&'a str>,
+}
+
+impl<'a> Settings<'a> {
+    fn from_config(config: &'a crate::config::Config) -> Option<Self> {
+        Some(Self {
+            site_key: config.turnstile_site_key.as_deref()?,
+            secret_key: config.turnstile_secret_key.as_deref()?,
+            pass_ttl: config.turnstile_pass_ttl,
+            font_url: config.font_url.as_deref(),
+        })
+    }
+}
+
+pub fn enabled(config: &crate::config::Config) -> bool {
+    Settings::from_config(config).is_some()
+}
+
+pub fn check(
+    request: &worker::Request,
+    config: &crate::config::Config,
+) -> worker::Result<Option<worker::Response>> {
+    let Some(settings) = Settings::from_config(config) else {
+        return Ok(None);
+    };
+    let url = request.url()?;
+    if crate::hotlink::is_redirect_target(config, &url)
+        || has_valid_pass(request, &settings)?
+        || crate::share::verify(config, &url, now())
+    {
+        return Ok(None);
+    }
+    challenge(config, &settings).map(Some)
+}
+
+pub async fn verify(
+    mut request: worker::Request,
+    environment: &worker::Env,
+    config: &crate::config::Config,
+) -> worker::Result<worker::Response> {
+    let Some(settings) = Settings::from_config(config) else {
+        return crate::errors::for_request(
+            &request,
+            environment,
+            config,
+            405,
+            "Method Not Allowed",
+        )
+        .await;
+    };
+
+    let form = request.text().await?;
+    let Some(token) = url::form_urlencoded::parse(form.as_bytes())
+        .find(|(name, _)| name == "cf-turnstile-response")
+        .map(|(_, value)| value.into_owned())
+    else {
+        return challenge(config, &settings);
+    };
+
+    let mut body = url::form_urlencoded::Serializer::new(String::new());
+    body.append_pair("secret", settings.secret_key)
+        .append_pair("response", &token);
+    if let Some(remote_ip) = request.headers().get("cf-connecting-ip")? {
+        body.append_pair("remoteip", &remote_ip);
+    }
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "application/x-www-form-urlencoded")?;
+    let mut init = worker::RequestInit::new();
+    init.with_method(worker::Method::Post)
+        .with_headers(headers)
+        .with_body(Some(body.finish().into()));
+    let outcome: SiteverifyResponse =
+        worker::Fetch::Request(worker::Request::new_with_init(SITEVERIFY_URL, &init)?)
+            .send()
+            .await?
+            .json()
+            .await?;
+    if !outcome.success {
+        return challenge(config, &settings);
+    }
+
+    let expires = now() + settings.pass_ttl;
+    let mut response = crate::redirect(request.url()?.as_str(), 303)?;
+    response.headers_mut().set(
+        "set-cookie",
+        &format!(
+            "{COOKIE_NAME}={expires}.{}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+            sign(settings.secret_key, expires),
+            settings.pass_ttl
+        ),
+    )?;
+    Ok(response)
+}
+
+fn challenge(
+    config: &crate::config::Config,
+    settings: &Settings,
+) -> worker::Result<worker::Response> {
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "text/html")?;
+    headers.set("cache-control", "no-store")?;
+    crate::security::page(config, &mut headers)?;
+    Ok(worker::Response::ok(
+        Challenge {
+            site_key: settings.site_key,
+            font_url: settings.font_url,
+        }
+        .to_string(),
+    )?
+    .with_status(403)
+    .with_headers(headers))
+}
+
+fn has_valid_pass(request: &worker::Request, settings: &Settings) -> worker::Result<bool> {
+    let Some(cookies) = request.headers().get("cookie")? else {
+        return Ok(false);
+    };
+    Ok(cookies
+        .split(';')
+        .filter_map(|cookie| cookie.trim().strip_prefix(COOKIE_NAME)?.strip_prefix('='))
+        .filter_map(|value| value.split_once('.'))
+        .any(|(expires, signature)| {
+            expires.parse::<u64>().is_ok_and(|expires| {
+                expires > now() && verify_signature(settings.secret_key, expires, signature)
+            })
+        }))
+}
+
+fn mac(secret_key: &str, expires: u64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret_key.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(expires.to_string().as_bytes());
+    mac
+}
+
+fn sign(secret_key: &str, expires: u64) -> String {
+    crate::hex(&mac(secret_key, expires).finalize().into_bytes())
+}
+
+fn verify_signature(secret_key: &str, expires: u64, signature: &str) -> bool {
+    crate::unhex(signature)
+        .is_some_and(|signature| mac(secret_key, expires).verify_slice(&signature).is_ok())
+}
+
+fn now() -> u64 {
+    worker::Date::now().as_millis() / 1000
+}
@@ -0,0 +1,130 @@
+use itertools::Itertools;
+
+pub async fn recent(
+    mount: &crate::mounts::Mount,
+    path: &str,
+    limit: usize,
+    filter: &crate::filter::EntryFilter,
+) -> worker::Result<Vec<(String, crate::store::Object)>> {
+    Ok(files(mount, path, filter)
+        .await?
+        .into_iter()
+        .sorted_by(|(_, left), (_, right)| right.uploaded.cmp(&left.uploaded))
+        .take(limit)
+        .collect())
+}
+
+pub async fn files(
+    mount: &crate::mounts::Mount,
+    path: &str,
+    filter: &crate::filter::EntryFilter,
+) -> worker::Result<Vec<(String, crate::store::Object)>> {
+    walk(mount, path, filter, None).await
+}
+
+/// Like `files`, but only the files directly in `path`, listed with a
+/// delimiter rather than by walking the subtree.
+pub async fn children(
+    mount: &crate::mounts::Mount,
+    path: &str,
+    filter: &crate::filter::EntryFilter,
+) -> worker::Result<Vec<(String, crate::store::Object)>> {
+    walk(mount, path, filter, Some("/")).await
+}
+
+async fn walk(
+    mount: &crate::mounts::Mount,
+    path: &str,
+    filter: &crate::filter::EntryFilter,
+    delimiter: Option<&str>,
+) -> worker::Result<Vec<(String, crate::store::Object)>> {
+    let key_prefix = mount.key(path);
+    let mut objects = std::collections::HashMap::new();
+    let mut entries = Vec::new();
+    let mut hidden = Vec::new();
+    let mut cursor = None;
+    loop {
+        let listing = mount
+            .store
+            .list(crate::store::ListOptions {
+                prefix: key_prefix.clone(),
+                delimiter: delimiter.map(str::to_owned),
+                cursor,
+                ..Default::default()
+            })
+            .await?;
+        for object in listing.objects {
+            let path = format!("{path}{}", &object.key[key_prefix.len()..]);
+            match path.strip_suffix(".noindex") {
+                Some(directory) if directory.is_empty() || directory.ends_with('/') => {
+                    hidden.push(directory.to_owned());
+                }
+                _ => {
+                    entries.push((
+                        crate::EntryType::File {
+                            size: object.size,
+                            uploaded: object.uploaded,
+                        },
+                        path.clone(),
+                    ));
+                    objects.insert(path, object);
+                }
+            }
+        }
+        match listing.cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    filter.apply(path, &mut entries);
+    Ok(entries
+        .into_iter()
+        .filter(|(_, path)| !hidden.iter().any(|directory| path.starts_with(directory)))
+        .filter_map(|(_, path)| objects.remove(&path).map(|object| (path, object)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn lists_children_without_the_subtree() {
+        let mount = crate::mounts::Mount {
+            store: std::rc::Rc::new(crate::store::MemoryStore::with_files(&[
+                ("docs/a.txt", "a"),
+                ("docs/.b.txt", "b"),
+                ("docs/nested/c.txt", "c"),
+                ("docs/private/.noindex", ""),
+                ("docs/private/d.txt", "d"),
+            ])),
+            binding: "BUCKET".into(),
+            path: String::new(),
+            key_prefix: String::new(),
+        };
+        let filter = crate::filter::EntryFilter::from_config(&Default::default(), false);
+        let paths = |files: Vec<(String, crate::store::Object)>| {
+            files.into_iter().map(|(path, _)| path).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(
+                children(&mount, "docs/", &filter)
+                    .now_or_never()
+                    .unwrap()
+                    .unwrap()
+            ),
+            ["docs/a.txt"]
+        );
+        assert_eq!(
+            paths(
+                files(&mount, "docs/", &filter)
+                    .now_or_never()
+                    .unwrap()
+                    .unwrap()
+            ),
+            ["docs/a.txt", "docs/nested/c.txt"]
+        );
+    }
+}
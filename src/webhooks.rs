@@ -0,0 +1,85 @@
+const DISCORD_HOSTS: [&str; 2] = ["discord.com", "discordapp.com"];
+
+#[derive(Clone, Copy)]
+pub enum Event {
+    Download,
+    NotFound,
+    QuotaExceeded,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::Download => "download",
+            Event::NotFound => "not_found",
+            Event::QuotaExceeded => "quota_exceeded",
+        }
+    }
+
+    fn summary(self) -> &'static str {
+        match self {
+            Event::Download => "Downloaded",
+            Event::NotFound => "Not found",
+            Event::QuotaExceeded => "Quota exceeded for",
+        }
+    }
+}
+
+pub fn notify(
+    config: &crate::config::Config,
+    context: &worker::Context,
+    request: &worker::Request,
+    event: Event,
+) -> worker::Result<()> {
+    let Some(webhook_url) = &config.webhook_url else {
+        return Ok(());
+    };
+    if !config
+        .webhook_events
+        .iter()
+        .any(|name| name == "*" || name == event.name())
+    {
+        return Ok(());
+    }
+    let url = request.url()?;
+    let path = urlencoding::decode(url.path())
+        .map(|path| path.into_owned())
+        .unwrap_or_else(|_| url.path().to_owned());
+    let body = if webhook_url
+        .host_str()
+        .is_some_and(|host| DISCORD_HOSTS.contains(&host))
+    {
+        let path = path.replace('`', "'");
+        serde_json::json!({
+            "content": format!("{} `{path}`", event.summary()),
+            "allowed_mentions": { "parse": [] },
+        })
+    } else {
+        serde_json::json!({
+            "event": event.name(),
+            "path": path,
+            "url": url.to_string(),
+            "country": request.headers().get("cf-ipcountry")?,
+            "user_agent": request.headers().get("user-agent")?,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })
+    };
+
+    let mut headers = worker::Headers::new();
+    headers.set("content-type", "application/json")?;
+    let mut init = worker::RequestInit::new();
+    init.with_method(worker::Method::Post)
+        .with_headers(headers)
+        .with_body(Some(body.to_string().into()));
+    let request = worker::Request::new_with_init(webhook_url.as_str(), &init)?;
+    context.wait_until(async move {
+        match worker::Fetch::Request(request).send().await {
+            Ok(response) if response.status_code() >= 300 => {
+                worker::console_error!("the webhook failed with status {}", response.status_code())
+            }
+            Ok(_) => {}
+            Err(err) => worker::console_error!("failed to call the webhook: {err}"),
+        }
+    });
+    Ok(())
+}